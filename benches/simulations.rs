@@ -0,0 +1,154 @@
+/*!
+Benchmarks for the hot paths of the simulation cores, to measure any redesign against.
+They only use the CPU, so `cargo bench` works on a box without a display or GPU.
+Everything random is seeded, so that runs can be compared.
+*/
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use nannou::{
+    image::{DynamicImage, Rgba, RgbaImage},
+    prelude::*,
+    rand::{rngs::StdRng, SeedableRng},
+};
+use std::hint::black_box;
+use workshop::{
+    geometry::Polygon,
+    simulations::{
+        boids::{self, Boid, Rules},
+        game_of_life::Life,
+        recursive_rotation, three_points,
+    },
+};
+
+const SEED: u64 = 0;
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(SEED)
+}
+
+/** Steers one boid by the rest of the flock */
+type Rule = fn(&mut Boid, &[Boid], &Rules);
+
+/** Each rule on its own for every boid in the flock, which is where the O(n²) neighbour loops are */
+fn boid_rules(c: &mut Criterion) {
+    let boundary = Rect::from_w_h(1600., 1200.);
+    let rules = Rules::default();
+    let rule_fns: [(&str, Rule); 3] = [
+        ("cohesion", boids::cohesion),
+        ("separation", |boid, flock, rules| {
+            boids::separation(boid, flock, rules.separation)
+        }),
+        ("alignment", boids::alignment),
+    ];
+
+    for (name, rule) in rule_fns {
+        let mut group = c.benchmark_group(format!("boids/{name}"));
+        group.sample_size(10);
+        for size in [100, 1_000, 10_000] {
+            let flock = boids::new_flock(size, &boundary, &mut rng());
+            group.throughput(Throughput::Elements(size as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &flock, |b, flock| {
+                b.iter_batched_ref(
+                    || flock.clone(),
+                    |steered| {
+                        for boid in steered.iter_mut() {
+                            rule(boid, flock, &rules);
+                        }
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+        group.finish();
+    }
+
+    let mut group = c.benchmark_group("boids/step");
+    group.sample_size(10);
+    for size in [100, 1_000, 10_000] {
+        let flock = boids::new_flock(size, &boundary, &mut rng());
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &flock, |b, flock| {
+            b.iter_batched_ref(
+                || flock.clone(),
+                |flock| boids::step(flock, boundary, &rules),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn life_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("game_of_life/step");
+    for size in [64, 256, 1024] {
+        let life = Life::random(size, size, &mut rng());
+        group.throughput(Throughput::Elements((size * size) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &life, |b, life| {
+            b.iter_batched_ref(|| life.clone(), Life::step, BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+/** A 1024² image with a gradient in it, as the picture doesn't change how long rotating takes */
+fn test_image() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(1024, 1024, |x, y| {
+        Rgba([(x / 4) as u8, (y / 4) as u8, ((x + y) / 8) as u8, 255])
+    }))
+}
+
+fn process_tiles(c: &mut Criterion) {
+    let image = test_image();
+    let mut group = c.benchmark_group("recursive_rotation/process_tiles");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(1024 * 1024));
+    // From four tiles, to a few hundred, to tiles two pixels wide
+    for layers in [2, 6, 10] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(layers),
+            &layers,
+            |b, &layers| {
+                b.iter(|| recursive_rotation::process_tiles(black_box(&image), layers, false))
+            },
+        );
+    }
+    group.finish();
+}
+
+fn color_secondary_points(c: &mut Criterion) {
+    let primary_points = [pt2(-200., -150.), pt2(250., 0.), pt2(0., 200.)];
+    let mut group = c.benchmark_group("three_points/color_secondary_points");
+    // The default point width, and the single pixels that the overlay is worried about
+    for point_width in [5., 1.] {
+        let mut points =
+            three_points::new_secondary_points(Rect::from_w_h(800., 600.), point_width);
+        group.throughput(Throughput::Elements(points.len() as u64));
+        group.bench_function(BenchmarkId::from_parameter(point_width), |b| {
+            b.iter(|| three_points::color_secondary_points(primary_points, &mut points, 255.))
+        });
+    }
+    group.finish();
+}
+
+fn distance_to_point(c: &mut Criterion) {
+    let mut group = c.benchmark_group("geometry/distance_to_point");
+    for sides in [3, 12, 100] {
+        let polygon = Polygon::new(100., sides);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(sides),
+            &polygon,
+            |b, polygon| b.iter(|| polygon.distance_to_point(black_box(pt2(150., 75.)))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    boid_rules,
+    life_step,
+    process_tiles,
+    color_secondary_points,
+    distance_to_point
+);
+criterion_main!(benches);
@@ -0,0 +1,286 @@
+use crate::{
+    config::ConfigFile,
+    host::HOST_KEYS,
+    sketch::{Context, Input},
+};
+use nannou::prelude::*;
+use std::collections::HashMap;
+
+/** Names of the keys that can be bound, as they are written in keys.toml and shown in the help */
+const KEY_NAMES: &[(Key, &str)] = &[
+    (Key::A, "A"),
+    (Key::B, "B"),
+    (Key::C, "C"),
+    (Key::D, "D"),
+    (Key::E, "E"),
+    (Key::F, "F"),
+    (Key::G, "G"),
+    (Key::H, "H"),
+    (Key::I, "I"),
+    (Key::J, "J"),
+    (Key::K, "K"),
+    (Key::L, "L"),
+    (Key::M, "M"),
+    (Key::N, "N"),
+    (Key::O, "O"),
+    (Key::P, "P"),
+    (Key::Q, "Q"),
+    (Key::R, "R"),
+    (Key::S, "S"),
+    (Key::T, "T"),
+    (Key::U, "U"),
+    (Key::V, "V"),
+    (Key::W, "W"),
+    (Key::X, "X"),
+    (Key::Y, "Y"),
+    (Key::Z, "Z"),
+    (Key::Key0, "0"),
+    (Key::Key1, "1"),
+    (Key::Key2, "2"),
+    (Key::Key3, "3"),
+    (Key::Key4, "4"),
+    (Key::Key5, "5"),
+    (Key::Key6, "6"),
+    (Key::Key7, "7"),
+    (Key::Key8, "8"),
+    (Key::Key9, "9"),
+    (Key::Space, "Space"),
+    (Key::Return, "Enter"),
+    (Key::Back, "Backspace"),
+    (Key::Delete, "Delete"),
+    (Key::Tab, "Tab"),
+    (Key::Escape, "Escape"),
+    (Key::Left, "Left"),
+    (Key::Right, "Right"),
+    (Key::Up, "Up"),
+    (Key::Down, "Down"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::Comma, "Comma"),
+    (Key::Period, "Period"),
+    (Key::Minus, "Minus"),
+    (Key::Equals, "Equals"),
+    (Key::Slash, "Slash"),
+    (Key::F1, "F1"),
+    (Key::F2, "F2"),
+    (Key::F3, "F3"),
+    (Key::F4, "F4"),
+    (Key::F5, "F5"),
+    (Key::F6, "F6"),
+    (Key::F7, "F7"),
+    (Key::F8, "F8"),
+    (Key::F9, "F9"),
+    (Key::F10, "F10"),
+    (Key::F11, "F11"),
+    (Key::F12, "F12"),
+];
+
+pub fn key_name(key: Key) -> String {
+    KEY_NAMES
+        .iter()
+        .find(|(named, _)| *named == key)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("{key:?}"))
+}
+
+/** Case doesn't matter, so that "space" and "Space" are the same key */
+pub fn parse_key(name: &str) -> Option<Key> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, named)| named.eq_ignore_ascii_case(name))
+        .map(|(key, _)| *key)
+}
+
+const BUTTON_NAMES: &[(MouseButton, &str)] = &[
+    (MouseButton::Left, "Left click"),
+    (MouseButton::Right, "Right click"),
+    (MouseButton::Middle, "Middle click"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trigger {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+impl Trigger {
+    /** A key name like "Space", or a button name like "Left click" */
+    pub fn parse(name: &str) -> Option<Self> {
+        parse_key(name).map(Trigger::Key).or_else(|| {
+            BUTTON_NAMES
+                .iter()
+                .find(|(_, named)| named.eq_ignore_ascii_case(name))
+                .map(|(button, _)| Trigger::Mouse(*button))
+        })
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Trigger::Key(key) => key_name(*key),
+            Trigger::Mouse(button) => BUTTON_NAMES
+                .iter()
+                .find(|(named, _)| named == button)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| format!("{button:?} click")),
+        }
+    }
+}
+
+/** Something a sketch does when a key or mouse button is pressed */
+#[derive(Clone, Copy, Debug)]
+pub struct Binding<A> {
+    pub action: A,
+    /** How the action is referred to in keys.toml */
+    pub name: &'static str,
+    pub trigger: Trigger,
+    /** Shown in the help overlay */
+    pub description: &'static str,
+}
+
+impl<A> Binding<A> {
+    pub const fn key(action: A, name: &'static str, key: Key, description: &'static str) -> Self {
+        Binding {
+            action,
+            name,
+            trigger: Trigger::Key(key),
+            description,
+        }
+    }
+
+    pub const fn mouse(
+        action: A,
+        name: &'static str,
+        button: MouseButton,
+        description: &'static str,
+    ) -> Self {
+        Binding {
+            action,
+            name,
+            trigger: Trigger::Mouse(button),
+            description,
+        }
+    }
+}
+
+/** Keys or buttons to rebind, by sketch and then by the name of the action */
+type KeysConfig = HashMap<String, HashMap<String, String>>;
+
+/**
+The inputs of a sketch, which dispatch actions and describe themselves in the help overlay.
+Keys can be rebound in assets/config/keys.toml, under a table named after the sketch,
+which is reloaded when it changes.
+*/
+pub struct Bindings<A: 'static> {
+    sketch: &'static str,
+    defaults: &'static [Binding<A>],
+    bindings: Vec<Binding<A>>,
+    config: ConfigFile<KeysConfig>,
+    /** Rebindings in the config that don't name a known action or key, take a host key or clash */
+    error: Option<String>,
+}
+
+impl<A: Copy> Bindings<A> {
+    pub fn new(ctx: &Context, sketch: &'static str, defaults: &'static [Binding<A>]) -> Self {
+        let mut bindings = Bindings {
+            sketch,
+            defaults,
+            bindings: defaults.to_vec(),
+            config: ConfigFile::load(ctx, "keys"),
+            error: None,
+        };
+        bindings.rebind();
+        bindings
+    }
+
+    fn rebind(&mut self) {
+        self.bindings = self.defaults.to_vec();
+        self.error = None;
+        let Some(keys) = self.config.get(self.sketch) else {
+            return;
+        };
+
+        let mut unknown = vec![];
+        for (name, trigger_name) in keys {
+            let binding = self
+                .bindings
+                .iter_mut()
+                .find(|binding| binding.name == name);
+            match (binding, Trigger::parse(trigger_name)) {
+                (_, Some(Trigger::Key(key))) if HOST_KEYS.contains(&key) => {
+                    unknown.push(format!("{} is kept for the host", key_name(key)))
+                }
+                (Some(binding), Some(trigger)) => binding.trigger = trigger,
+                (None, _) => unknown.push(format!("no action called {name}")),
+                (_, None) => unknown.push(format!("no key called {trigger_name}")),
+            }
+        }
+        // Only the first action bound to a trigger is ever found, so the others would never fire
+        for (index, binding) in self.bindings.iter().enumerate() {
+            if let Some(earlier) = self.bindings[..index]
+                .iter()
+                .find(|earlier| earlier.trigger == binding.trigger)
+            {
+                unknown.push(format!(
+                    "{} is bound to both {} and {}",
+                    binding.trigger.name(),
+                    earlier.name,
+                    binding.name
+                ));
+            }
+        }
+        if !unknown.is_empty() {
+            unknown.sort();
+            self.error = Some(format!(
+                "keys.toml [{}]: {}",
+                self.sketch,
+                unknown.join(", ")
+            ));
+        }
+    }
+
+    /** Pick up changes to keys.toml, call this every update */
+    pub fn reload_if_changed(&mut self) {
+        if self.config.reload_if_changed() {
+            self.rebind();
+        }
+    }
+
+    /** The action bound to a key or mouse button that was just pressed */
+    pub fn pressed(&self, input: &Input) -> Option<A> {
+        let trigger = match input {
+            Input::KeyPressed(key) => Trigger::Key(*key),
+            Input::MousePressed { button, .. } => Trigger::Mouse(*button),
+            Input::KeyReleased(_) => return None,
+        };
+        self.action(trigger)
+    }
+
+    /** The action bound to a key that was just released, for actions that last while held */
+    pub fn released(&self, input: &Input) -> Option<A> {
+        match input {
+            Input::KeyReleased(key) => self.action(Trigger::Key(*key)),
+            _ => None,
+        }
+    }
+
+    fn action(&self, trigger: Trigger) -> Option<A> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.trigger == trigger)
+            .map(|binding| binding.action)
+    }
+
+    /** What to press and what it does, for the help overlay */
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        self.bindings
+            .iter()
+            .map(|binding| (binding.trigger.name(), binding.description))
+            .collect()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.config.error().or(self.error.as_deref())
+    }
+}
@@ -0,0 +1,61 @@
+use nannou::prelude::*;
+
+pub const MIN_ZOOM: f32 = 1. / 8.;
+pub const MAX_ZOOM: f32 = 64.;
+
+/**
+A view onto the world a sketch draws in, which can be panned and zoomed.
+Sketches keep drawing in window coordinates, which the host calls the world,
+and the camera maps them to the screen and the mouse back to them.
+Screen coordinates are centred on the window with y going up, like nannou's.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /** The point of the world in the middle of the window */
+    pub centre: Point2,
+    /** Screen points per world unit */
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            centre: pt2(0., 0.),
+            zoom: 1.,
+        }
+    }
+}
+
+impl Camera {
+    pub fn to_world(&self, screen: Point2) -> Point2 {
+        self.centre + screen / self.zoom
+    }
+
+    pub fn to_screen(&self, world: Point2) -> Point2 {
+        (world - self.centre) * self.zoom
+    }
+
+    /** Move the view with the mouse, so that what was under it stays under it */
+    pub fn pan(&mut self, screen_delta: Vec2) {
+        self.centre -= screen_delta / self.zoom;
+    }
+
+    /** Zoom in by factor, or out if it is below 1, keeping the world under screen_point in place */
+    pub fn zoom_at(&mut self, screen_point: Point2, factor: f32) {
+        let anchor = self.to_world(screen_point);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.centre = anchor - screen_point / self.zoom;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Camera::default();
+    }
+
+    /** The part of the world that can be seen in a window */
+    pub fn visible_rect(&self, window: Rect) -> Rect {
+        Rect::from_corners(
+            self.to_world(window.bottom_left()),
+            self.to_world(window.top_right()),
+        )
+    }
+}
@@ -0,0 +1,166 @@
+use nannou::{
+    color::{Hsv, IntoLinSrgba, RgbHue, Srgb, Srgba},
+    image::DynamicImage,
+    prelude::*,
+};
+use std::sync::Arc;
+
+/** Colours and outline of a shape, mirroring the defaults of nannou's Draw */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    /** Fill colour, or the colour of the line itself for lines and polylines */
+    pub color: Option<Srgba>,
+    pub stroke: Option<Srgba>,
+    /** Width of the outline, or of the line itself for lines and polylines */
+    pub stroke_weight: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            color: Some(Srgba::new(1., 1., 1., 1.)),
+            stroke: None,
+            stroke_weight: 1.,
+        }
+    }
+}
+
+impl Style {
+    pub fn color<C: IntoLinSrgba<f32>>(&mut self, color: C) -> &mut Self {
+        self.color = Some(to_srgba(color));
+        self
+    }
+
+    pub fn rgb(&mut self, r: f32, g: f32, b: f32) -> &mut Self {
+        self.color(Srgb::new(r, g, b))
+    }
+
+    /** Hue is in turns, like nannou's hsv */
+    pub fn hsv(&mut self, h: f32, s: f32, v: f32) -> &mut Self {
+        self.color(Hsv::new(RgbHue::from_degrees(h * 360.), s, v))
+    }
+
+    pub fn no_fill(&mut self) -> &mut Self {
+        self.color = None;
+        self
+    }
+
+    pub fn stroke<C: IntoLinSrgba<f32>>(&mut self, color: C) -> &mut Self {
+        self.stroke = Some(to_srgba(color));
+        self
+    }
+
+    pub fn stroke_weight(&mut self, weight: f32) -> &mut Self {
+        self.stroke_weight = weight;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Geometry {
+    Polygon(Vec<Point2>),
+    Polyline(Vec<Point2>),
+    Rect(Rect),
+    Ellipse {
+        centre: Point2,
+        radius: f32,
+    },
+    Line {
+        start: Point2,
+        end: Point2,
+    },
+    Image {
+        image: Arc<DynamicImage>,
+        rect: Rect,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Shape {
+    pub geometry: Geometry,
+    pub style: Style,
+}
+
+/**
+The shapes a sketch draws in one frame, in window coordinates.
+Backends turn these into a nannou Draw, pixels or vectors.
+*/
+#[derive(Clone, Debug, Default)]
+pub struct Canvas {
+    pub background: Option<Srgba>,
+    pub shapes: Vec<Shape>,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Canvas::default()
+    }
+
+    pub fn background<C: IntoLinSrgba<f32>>(&mut self, color: C) {
+        self.background = Some(to_srgba(color));
+    }
+
+    pub fn polygon<I: IntoIterator<Item = Point2>>(&mut self, points: I) -> &mut Style {
+        self.push(Geometry::Polygon(points.into_iter().collect()))
+    }
+
+    pub fn tri(&mut self, a: Point2, b: Point2, c: Point2) -> &mut Style {
+        self.polygon([a, b, c])
+    }
+
+    pub fn polyline<I: IntoIterator<Item = Point2>>(&mut self, points: I) -> &mut Style {
+        self.push(Geometry::Polyline(points.into_iter().collect()))
+    }
+
+    pub fn rect(&mut self, rect: Rect) -> &mut Style {
+        self.push(Geometry::Rect(rect))
+    }
+
+    pub fn ellipse(&mut self, centre: Point2, radius: f32) -> &mut Style {
+        self.push(Geometry::Ellipse { centre, radius })
+    }
+
+    pub fn line(&mut self, start: Point2, end: Point2) -> &mut Style {
+        self.push(Geometry::Line { start, end })
+    }
+
+    pub fn image(&mut self, image: Arc<DynamicImage>, rect: Rect) {
+        self.push(Geometry::Image { image, rect });
+    }
+
+    /**
+    Draw another canvas over this one, with its colours made more transparent by opacity.
+    Its background becomes a rect covering window, so that it fades in too.
+    Images are drawn as they are, as not every backend can make them transparent.
+    */
+    pub fn overlay(&mut self, other: Canvas, window: Rect, opacity: f32) {
+        let start = self.shapes.len();
+        if let Some(color) = other.background {
+            self.rect(window).color(color);
+        }
+        self.shapes.extend(other.shapes);
+
+        for shape in &mut self.shapes[start..] {
+            let style = &mut shape.style;
+            for color in [&mut style.color, &mut style.stroke].into_iter().flatten() {
+                color.alpha *= opacity;
+            }
+        }
+    }
+
+    fn push(&mut self, geometry: Geometry) -> &mut Style {
+        self.shapes.push(Shape {
+            geometry,
+            style: Style::default(),
+        });
+        &mut self
+            .shapes
+            .last_mut()
+            .expect("A shape was just pushed")
+            .style
+    }
+}
+
+fn to_srgba<C: IntoLinSrgba<f32>>(color: C) -> Srgba {
+    Srgba::from_linear(color.into_lin_srgba())
+}
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+/** Frames longer than this are cut short, so that a stall doesn't turn into a burst of steps */
+const MAX_FRAME_TIME: f32 = 0.25;
+
+pub const MIN_SPEED: f32 = 1. / 16.;
+pub const MAX_SPEED: f32 = 16.;
+
+/** Something done to a clock from outside the sketch, which is kept so that it can be replayed */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockAction {
+    TogglePaused,
+    Step,
+    SetSpeed(f32),
+}
+
+/**
+Turns the time between frames into a whole number of fixed size simulation steps,
+so that a simulation runs at the same pace whatever the frame rate, in a window or headless.
+Sketches own one, ask it how many steps to take in update, and hand it to the host through
+Sketch::clock so that it can be paused, stepped and sped up the same way in every sketch.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct SimClock {
+    /** Simulated seconds per step */
+    step: f32,
+    /** Simulated seconds per real second */
+    speed: f32,
+    paused: bool,
+    /** Simulated time that hasn't been stepped through yet, kept in f64 so it doesn't drift */
+    accumulator: f64,
+    /** Steps asked for by step_once, taken on the next update even while paused */
+    queued_steps: u32,
+}
+
+impl SimClock {
+    pub fn new(steps_per_second: f32) -> Self {
+        SimClock {
+            step: 1. / steps_per_second,
+            speed: 1.,
+            paused: false,
+            accumulator: 0.,
+            queued_steps: 0,
+        }
+    }
+
+    /** Simulated seconds that pass in each step */
+    pub fn dt(&self) -> f32 {
+        self.step
+    }
+
+    pub fn set_steps_per_second(&mut self, steps_per_second: f32) {
+        self.step = 1. / steps_per_second;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.accumulator = 0.;
+    }
+
+    pub fn toggle_paused(&mut self) {
+        self.set_paused(!self.paused);
+    }
+
+    /** Take one step on the next update, which is how a paused simulation is advanced */
+    pub fn step_once(&mut self) {
+        self.queued_steps += 1;
+    }
+
+    /** Throw away time and steps that haven't been taken yet, like when a simulation restarts */
+    pub fn reset(&mut self) {
+        self.accumulator = 0.;
+        self.queued_steps = 0;
+    }
+
+    pub fn apply(&mut self, action: ClockAction) {
+        match action {
+            ClockAction::TogglePaused => self.toggle_paused(),
+            ClockAction::Step => self.step_once(),
+            ClockAction::SetSpeed(speed) => self.set_speed(speed),
+        }
+    }
+
+    /** How many steps to take for a frame that lasted frame_time seconds */
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        let queued = std::mem::take(&mut self.queued_steps);
+        if self.paused {
+            return queued;
+        }
+
+        self.accumulator += (frame_time.min(MAX_FRAME_TIME) * self.speed) as f64;
+        let step = self.step as f64;
+        // Allow for rounding, so that a frame exactly some steps long always takes that many,
+        // even when it is hundreds of steps long and the step isn't exact in f32
+        let steps = (self.accumulator / step + 1e-4).floor();
+        self.accumulator = (self.accumulator - steps * step).max(0.);
+        queued + steps as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_the_remainder_over_to_the_next_frame() {
+        let mut clock = SimClock::new(10.);
+        assert_eq!(clock.advance(0.25), 2);
+        assert_eq!(clock.advance(0.04), 0);
+        assert_eq!(clock.advance(0.01), 1);
+        assert_eq!(clock.advance(0.1), 1);
+    }
+
+    #[test]
+    fn frames_exactly_a_step_long_take_a_step() {
+        let mut clock = SimClock::new(60.);
+        for _ in 0..600 {
+            assert_eq!(clock.advance(1. / 60.), 1);
+        }
+    }
+
+    #[test]
+    fn steps_once_while_paused() {
+        let mut clock = SimClock::new(10.);
+        clock.set_paused(true);
+        assert_eq!(clock.advance(1.), 0);
+        clock.step_once();
+        clock.step_once();
+        assert_eq!(clock.advance(0.01), 2);
+        assert_eq!(clock.advance(0.01), 0);
+
+        // Time that passed while paused isn't made up for afterwards
+        clock.set_paused(false);
+        assert_eq!(clock.advance(0.05), 0);
+        assert_eq!(clock.advance(0.05), 1);
+    }
+
+    #[test]
+    fn changing_steps_per_second_keeps_the_time_so_far() {
+        let mut clock = SimClock::new(10.);
+        assert_eq!(clock.advance(0.05), 0);
+        clock.set_steps_per_second(20.);
+        assert_eq!(clock.dt(), 0.05);
+        assert_eq!(clock.advance(0.), 1);
+        assert_eq!(clock.advance(0.1), 2);
+    }
+
+    #[test]
+    fn caps_how_far_a_long_frame_catches_up() {
+        let mut clock = SimClock::new(60.);
+        assert_eq!(clock.advance(10.), 15);
+        assert_eq!(clock.advance(0.), 0);
+
+        clock.set_speed(100.);
+        assert_eq!(clock.speed(), MAX_SPEED);
+        assert_eq!(clock.advance(10.), 240);
+    }
+
+    #[test]
+    fn reset_drops_time_and_queued_steps() {
+        let mut clock = SimClock::new(10.);
+        clock.advance(0.09);
+        clock.step_once();
+        clock.reset();
+        assert_eq!(clock.advance(0.01), 0);
+    }
+}
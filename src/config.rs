@@ -0,0 +1,70 @@
+use crate::{presets, sketch::Context};
+use serde::de::DeserializeOwned;
+use std::{fs, ops::Deref, path::PathBuf, time::SystemTime};
+
+/**
+Parameters of a sketch read from assets/config/<sketch>.toml, which is watched while the sketch runs.
+If the file is missing the defaults are used. If it doesn't parse, the last good values are kept
+and the error is kept for the host to show over the sketch.
+*/
+#[derive(Clone, Debug)]
+pub struct ConfigFile<T> {
+    path: PathBuf,
+    values: T,
+    /** When the file was last read, or None if it hasn't been */
+    modified: Option<SystemTime>,
+    error: Option<String>,
+}
+
+impl<T: DeserializeOwned + Default> ConfigFile<T> {
+    pub fn load(ctx: &Context, sketch: &str) -> Self {
+        let mut config = ConfigFile {
+            path: ctx
+                .assets_path()
+                .join("config")
+                .join(sketch)
+                .with_extension("toml"),
+            values: T::default(),
+            modified: None,
+            error: None,
+        };
+        config.reload_if_changed();
+        config
+    }
+
+    /** Read the file again if it changed since it was last read, returns whether the values changed */
+    pub fn reload_if_changed(&mut self) -> bool {
+        let Ok(modified) = fs::metadata(&self.path).and_then(|metadata| metadata.modified()) else {
+            return false;
+        };
+        if self.modified == Some(modified) {
+            return false;
+        }
+        self.modified = Some(modified);
+
+        match presets::load(&self.path) {
+            Ok(values) => {
+                self.values = values;
+                self.error = None;
+                true
+            }
+            Err(error) => {
+                self.error = Some(format!("{}: {error}", self.path.display()));
+                false
+            }
+        }
+    }
+
+    /** Why the file couldn't be read the last time it changed */
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+impl<T> Deref for ConfigFile<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.values
+    }
+}
@@ -0,0 +1,425 @@
+/*!
+A WebSocket server on localhost, for other software on the same machine to drive a sketch's settings.
+
+Messages are JSON text. Clients send
+`{"type": "set", "parameter": "separation", "value": 2.5}` to change one setting,
+with nested settings addressed by a path like `rules/separation`,
+or `{"type": "get"}` to be sent the settings again.
+The server sends `{"type": "settings", "sketch": "boids", "values": {...}}` when a client connects
+and to every client whenever the settings change however they were changed,
+the same with `"requested": true` in reply to a get,
+and `{"type": "error", "message": "..."}` to a client whose message couldn't be applied.
+*/
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+/** How long a connection waits for a message before checking for settings to send */
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/** How long a client waits for the sketch to answer a get, which it does within a frame if it is running */
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Get,
+    Set { parameter: String, value: Value },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reply {
+    Settings {
+        sketch: String,
+        values: Value,
+        /** Whether these are the answer to a get, rather than sent because they changed */
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        requested: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl Reply {
+    /** The settings of a sketch as they are sent to clients, null if it has none */
+    pub fn settings(sketch: &str, values: Option<Value>) -> Self {
+        Reply::Settings {
+            sketch: sketch.to_string(),
+            values: values.unwrap_or(Value::Null),
+            requested: false,
+        }
+    }
+
+    /** The settings of a sketch in answer to a get */
+    pub fn answer(sketch: &str, values: Option<Value>) -> Self {
+        Reply::Settings {
+            sketch: sketch.to_string(),
+            values: values.unwrap_or(Value::Null),
+            requested: true,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        serde_json::to_string(self).expect("Replies should serialise to JSON")
+    }
+}
+
+/** A request from a client, with the way back to that client */
+pub struct ClientRequest {
+    pub request: Request,
+    reply: Sender<String>,
+}
+
+impl ClientRequest {
+    /** Send something to the client that made the request, which may have gone */
+    pub fn reply(&self, reply: &Reply) {
+        let _ = self.reply.send(reply.to_text());
+    }
+}
+
+/** What connections share with the server, to be sent the settings */
+#[derive(Default)]
+struct Clients {
+    senders: Vec<Sender<String>>,
+    /** The last settings message, for clients that connect after it was sent */
+    latest: Option<String>,
+}
+
+/** Accepts clients on its own threads, and hands their requests to the host once a frame */
+pub struct ControlServer {
+    sketch: &'static str,
+    port: u16,
+    clients: Arc<Mutex<Clients>>,
+    requests: Receiver<ClientRequest>,
+    /** Settings as they were last sent, so that they are only sent again when they change */
+    published: Option<Value>,
+}
+
+impl ControlServer {
+    /** Listen on localhost only, as the settings are nobody else's business */
+    pub fn start(sketch: &'static str, port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let port = listener.local_addr()?.port();
+        let clients = Arc::new(Mutex::new(Clients::default()));
+        let (requests_sender, requests) = mpsc::channel();
+
+        let shared = clients.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let clients = shared.clone();
+                let requests = requests_sender.clone();
+                thread::spawn(move || serve(stream, &clients, &requests));
+            }
+        });
+
+        Ok(ControlServer {
+            sketch,
+            port,
+            clients,
+            requests,
+            published: None,
+        })
+    }
+
+    /** The port that was listened on, which is picked by the system when started with 0 */
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /** Requests that arrived since the last call */
+    pub fn requests(&self) -> Vec<ClientRequest> {
+        self.requests.try_iter().collect()
+    }
+
+    /** Send the settings to every client, if they changed since they were last sent */
+    pub fn publish(&mut self, values: Option<Value>) {
+        let values = values.unwrap_or(Value::Null);
+        if self.published.as_ref() == Some(&values) {
+            return;
+        }
+        let text = Reply::settings(self.sketch, Some(values.clone())).to_text();
+        self.published = Some(values);
+
+        let mut clients = self
+            .clients
+            .lock()
+            .expect("Control threads shouldn't panic");
+        // Clients that have gone dropped their receiver
+        clients
+            .senders
+            .retain(|sender| sender.send(text.clone()).is_ok());
+        clients.latest = Some(text);
+    }
+}
+
+/**
+Change one parameter in a copy of some settings, addressed by the names of the fields leading to it.
+Returns None if there isn't a parameter with that name.
+*/
+pub fn set_parameter(values: &Value, parameter: &str, value: Value) -> Option<Value> {
+    let mut values = values.clone();
+    *values.pointer_mut(&format!("/{}", parameter.trim_start_matches('/')))? = value;
+    Some(values)
+}
+
+/** Talk to one client until it disconnects */
+fn serve(stream: TcpStream, clients: &Mutex<Clients>, requests: &Sender<ClientRequest>) {
+    let Ok(mut socket) = tungstenite::accept(stream) else {
+        return;
+    };
+    // Reads time out so that settings can be sent between them
+    if socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .is_err()
+    {
+        return;
+    }
+
+    let (reply, outgoing) = mpsc::channel();
+    {
+        let mut clients = clients.lock().expect("Control threads shouldn't panic");
+        if let Some(latest) = &clients.latest {
+            let _ = reply.send(latest.clone());
+        }
+        clients.senders.push(reply.clone());
+    }
+
+    loop {
+        for text in outgoing.try_iter() {
+            if socket.send(Message::Text(text)).is_err() {
+                return;
+            }
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(request) => {
+                    let request = ClientRequest {
+                        request,
+                        reply: reply.clone(),
+                    };
+                    if requests.send(request).is_err() {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    let message = format!("invalid request: {error}");
+                    let _ = reply.send(Reply::Error { message }.to_text());
+                }
+            },
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(error)) if is_timeout(&error) => (),
+            Err(_) => return,
+        }
+    }
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/** A connection to a sketch's control server */
+pub struct ControlClient {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl ControlClient {
+    pub fn connect(port: u16) -> Result<Self, Box<tungstenite::Error>> {
+        let (socket, _) = tungstenite::connect(format!("ws://{}:{port}", Ipv4Addr::LOCALHOST))?;
+        Ok(ControlClient { socket })
+    }
+
+    fn send(&mut self, request: &Request) -> Result<(), Box<tungstenite::Error>> {
+        let text = serde_json::to_string(request).expect("Requests should serialise to JSON");
+        Ok(self.socket.send(Message::Text(text))?)
+    }
+
+    /** Change one setting, which is applied on the sketch's next frame */
+    pub fn set(&mut self, parameter: &str, value: Value) -> Result<(), Box<tungstenite::Error>> {
+        self.send(&Request::Set {
+            parameter: parameter.to_string(),
+            value,
+        })
+    }
+
+    /**
+    Ask for the settings, which come after any sets sent before.
+    Errors the sketch sends back for those sets are printed.
+    */
+    pub fn get(&mut self) -> Result<Value, Box<tungstenite::Error>> {
+        self.send(&Request::Get)?;
+        self.set_timeout(Some(ANSWER_TIMEOUT))?;
+        let answer = loop {
+            match self.read() {
+                Err(error) if matches!(*error, tungstenite::Error::Io(ref io) if is_timeout(io)) => {
+                    let message =
+                        "the sketch didn't answer, it may be paused in a debugger or stuck";
+                    return Err(Box::new(
+                        io::Error::new(ErrorKind::TimedOut, message).into(),
+                    ));
+                }
+                Err(error) => return Err(error),
+                Ok(Reply::Settings {
+                    values,
+                    requested: true,
+                    ..
+                }) => break values,
+                Ok(_) => (),
+            }
+        };
+        self.set_timeout(None)?;
+        Ok(answer)
+    }
+
+    /** Wait for the settings to change, however long that takes */
+    pub fn changed_settings(&mut self) -> Result<Value, Box<tungstenite::Error>> {
+        loop {
+            if let Reply::Settings {
+                values,
+                requested: false,
+                ..
+            } = self.read()?
+            {
+                return Ok(values);
+            }
+        }
+    }
+
+    pub fn close(mut self) -> Result<(), Box<tungstenite::Error>> {
+        Ok(self.socket.close(None)?)
+    }
+
+    /** The next reply, printing errors as they come */
+    fn read(&mut self) -> Result<Reply, Box<tungstenite::Error>> {
+        loop {
+            let Message::Text(text) = self.socket.read()? else {
+                continue;
+            };
+            match serde_json::from_str(&text) {
+                Ok(Reply::Error { message }) => eprintln!("{message}"),
+                Ok(reply) => return Ok(reply),
+                Err(error) => eprintln!("Unexpected message from the sketch: {error}"),
+            }
+        }
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), Box<tungstenite::Error>> {
+        match self.socket.get_ref() {
+            MaybeTlsStream::Plain(stream) => Ok(stream
+                .set_read_timeout(timeout)
+                .map_err(tungstenite::Error::Io)?),
+            _ => Ok(()),
+        }
+    }
+}
+
+/**
+Connect to a sketch's control server, set the given parameters and print the settings they lead to.
+Keeps printing the settings every time they change if watch is set.
+*/
+pub fn client(
+    port: u16,
+    assignments: &[(String, Value)],
+    watch: bool,
+) -> Result<(), Box<tungstenite::Error>> {
+    let mut client = ControlClient::connect(port)?;
+    for (parameter, value) in assignments {
+        client.set(parameter, value.clone())?;
+    }
+    let print = |values: &Value| {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(values).unwrap_or_default()
+        );
+    };
+    print(&client.get()?);
+    if !watch {
+        return client.close();
+    }
+    loop {
+        print(&client.changed_settings()?);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::thread::JoinHandle;
+
+    /** Answer requests like the host does once a frame, until the client is done */
+    fn host<T>(server: &mut ControlServer, mut values: Option<Value>, client: JoinHandle<T>) -> T {
+        while !client.is_finished() {
+            for client in server.requests() {
+                match &client.request {
+                    Request::Get => client.reply(&Reply::answer("test", values.clone())),
+                    Request::Set { parameter, value } => {
+                        match values
+                            .as_ref()
+                            .and_then(|values| set_parameter(values, parameter, value.clone()))
+                        {
+                            Some(changed) => values = Some(changed),
+                            None => client.reply(&Reply::Error {
+                                message: format!("no parameter {parameter}"),
+                            }),
+                        }
+                    }
+                }
+            }
+            if values.is_some() {
+                server.publish(values.clone());
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        client.join().expect("Client shouldn't panic")
+    }
+
+    #[test]
+    fn sets_and_gets_settings() {
+        let mut server = ControlServer::start("test", 0).unwrap();
+        let port = server.port();
+        let client = thread::spawn(move || {
+            let mut client = ControlClient::connect(port).unwrap();
+            client.set("separation", json!(2.5)).unwrap();
+            client.set("rules/cohesion", json!(3)).unwrap();
+            client.set("missing", json!(1)).unwrap();
+            let values = client.get().unwrap();
+            client.close().unwrap();
+            values
+        });
+        let values = json!({"separation": 1.0, "rules": {"cohesion": 1}});
+        assert_eq!(
+            host(&mut server, Some(values), client),
+            json!({"separation": 2.5, "rules": {"cohesion": 3}})
+        );
+    }
+
+    #[test]
+    fn gets_settings_that_were_never_published() {
+        let mut server = ControlServer::start("test", 0).unwrap();
+        let port = server.port();
+        let client = thread::spawn(move || ControlClient::connect(port).unwrap().get().unwrap());
+        assert_eq!(host(&mut server, None, client), Value::Null);
+    }
+
+    #[test]
+    fn client_prints_settings_once() {
+        let mut server = ControlServer::start("test", 0).unwrap();
+        let port = server.port();
+        let assignments = [("speed".to_string(), json!(4))];
+        let client = thread::spawn(move || client(port, &assignments, false).is_ok());
+        assert!(host(&mut server, Some(json!({"speed": 1})), client));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    canvas::Canvas,
+    host::{draw_canvas, ImageTextures},
+    presets,
+    registry::SketchEntry,
+    sketch::{AnySketch, Context},
+};
+use nannou::{
+    prelude::*,
+    rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng},
+};
+use std::{mem, sync::OnceLock};
+
+/** Options from the command line, which nannou's model function has no way to be given */
+static GALLERY_OPTIONS: OnceLock<GalleryOptions> = OnceLock::new();
+
+pub struct GalleryOptions {
+    /** Shown in this order, starting again from the first after the last */
+    pub sketches: Vec<&'static SketchEntry>,
+    /** Seconds that each sketch is shown for, including fading in */
+    pub duration: f32,
+    /** Seconds that it takes for the next sketch to fade in over the last */
+    pub fade: f32,
+    /** Whether sketches start with one of their presets, when they have any */
+    pub presets: bool,
+    /** Picks the seed of each sketch, so that a whole gallery can be repeated */
+    pub seed: u64,
+}
+
+struct Showing {
+    sketch: Box<dyn AnySketch>,
+    seed: u64,
+}
+
+struct Gallery {
+    rng: StdRng,
+    current: Showing,
+    /** The sketch that current is fading in over */
+    previous: Option<Showing>,
+    /** Index in the options of the sketch that is currently showing */
+    index: usize,
+    /** Seconds that current has been showing for */
+    shown_for: f32,
+    textures: ImageTextures,
+}
+
+/** Show sketches one after another in a full screen window, until a key or button is pressed */
+pub fn run(options: GalleryOptions) {
+    if GALLERY_OPTIONS.set(options).is_err() {
+        panic!("Only one gallery should be run per process");
+    }
+    nannou::app(model).update(update).run();
+}
+
+fn options() -> &'static GalleryOptions {
+    GALLERY_OPTIONS
+        .get()
+        .expect("Gallery options should be set by run")
+}
+
+fn model(app: &App) -> Gallery {
+    app.new_window()
+        .title("workshop gallery")
+        .fullscreen()
+        .view(view)
+        .key_pressed(quit)
+        .mouse_pressed(quit)
+        .build()
+        .unwrap();
+
+    let options = options();
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let first = options
+        .sketches
+        .first()
+        .expect("Gallery should have at least one sketch");
+    Gallery {
+        current: start(app, first, &mut rng),
+        rng,
+        previous: None,
+        index: 0,
+        shown_for: 0.,
+        textures: ImageTextures::default(),
+    }
+}
+
+fn quit<E>(app: &App, _gallery: &mut Gallery, _event: E) {
+    app.quit();
+}
+
+/** Start a sketch with its own seed, and a random preset if asked for */
+fn start(app: &App, entry: &'static SketchEntry, rng: &mut StdRng) -> Showing {
+    let seed = rng.gen();
+    let ctx = Context::from_app(app, seed);
+    let mut sketch = (entry.init)(&ctx);
+    println!("Showing {} with seed {seed}", entry.name);
+
+    if options().presets {
+        let files = presets::files(&presets::folder(&ctx, entry.name));
+        if let Some(path) = files.choose(rng) {
+            match sketch.load_preset(&ctx, path) {
+                Ok(()) => println!("Using preset {}", path.display()),
+                Err(error) => eprintln!("Failed to load {}: {error}", path.display()),
+            }
+        }
+    }
+    if let Some(error) = sketch.error() {
+        eprintln!("{error}");
+    }
+    sketch.autoplay(&ctx);
+
+    Showing { sketch, seed }
+}
+
+fn update(app: &App, gallery: &mut Gallery, update: Update) {
+    let options = options();
+    let dt = update.since_last.as_secs_f32();
+    gallery.shown_for += dt;
+
+    // The previous sketch keeps moving while it fades out
+    for showing in [Some(&mut gallery.current), gallery.previous.as_mut()]
+        .into_iter()
+        .flatten()
+    {
+        showing
+            .sketch
+            .update(&Context::from_app(app, showing.seed), dt);
+    }
+
+    if gallery.shown_for >= options.fade {
+        gallery.previous = None;
+    }
+    if gallery.shown_for >= options.duration && options.sketches.len() > 1 {
+        gallery.index = (gallery.index + 1) % options.sketches.len();
+        let next = start(app, options.sketches[gallery.index], &mut gallery.rng);
+        gallery.previous = Some(mem::replace(&mut gallery.current, next));
+        gallery.shown_for = 0.;
+    }
+}
+
+fn view(app: &App, gallery: &Gallery, frame: Frame) {
+    let window = app.window_rect();
+    let mut canvas = Canvas::new();
+    // Sketches that don't clear the window shouldn't draw over what came before them
+    canvas.background(BLACK);
+
+    let draw_showing = |canvas: &mut Canvas, showing: &Showing, opacity: f32| {
+        let mut layer = Canvas::new();
+        showing
+            .sketch
+            .draw(&Context::from_app(app, showing.seed), &mut layer);
+        canvas.overlay(layer, window, opacity);
+    };
+    match &gallery.previous {
+        Some(previous) => {
+            draw_showing(&mut canvas, previous, 1.);
+            let opacity = (gallery.shown_for / options().fade).min(1.);
+            draw_showing(&mut canvas, &gallery.current, opacity);
+        }
+        None => draw_showing(&mut canvas, &gallery.current, 1.),
+    }
+
+    let draw = app.draw();
+    draw_canvas(app, &canvas, &draw, &gallery.textures);
+    draw.to_frame(app, &frame).unwrap();
+}
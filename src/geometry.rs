@@ -1,73 +1,332 @@
-use core::panic;
-use geo::{ClosestPoint, Coord, LineString};
+use geo::{BooleanOps, ClosestPoint, Coord, LineString, MultiPolygon};
 use geom::{bounding_rect, centroid};
 use nannou::prelude::*;
-use std::ops::{Add, Mul, Sub};
+use std::{
+    error::Error,
+    fmt,
+    ops::{Add, Mul, Sub},
+};
 
+pub const NO_VERTICES_ERROR: &str = "Polygon should have at least one vertex";
+
+/**
+Distances below this are treated as zero, so points this close are the same point,
+and lines whose ends stray this little from each other's are parallel
+*/
+pub const EPSILON: f32 = 1e-3;
+
+/** How far a line goes past the two points that define it */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extent {
+    /** Forever both ways */
+    Line,
+    /** From point1 through point2 and on forever */
+    Ray,
+    /** From point1 to point2 */
+    Segment,
+}
+
+/**
+The points point1 + t * (point2 - point1), for any t on a line,
+t of at least 0 on a ray, and t from 0 to 1 on a segment.
+A line whose points are the same is a single point, and never has a gradient.
+*/
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Line {
     pub point1: Point2,
     pub point2: Point2,
+    pub extent: Extent,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum LineIntersection {
+/** Where two lines meet */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Intersection {
+    None,
     Point(Point2),
-    Coincident,
-    Parallel,
+    /** Where collinear lines overlap, which can be a line, ray or segment */
+    Overlap(Line),
 }
 
 impl Line {
-    fn from_point_gradient(point: Point2, m: f32) -> Self {
-        // y - y1 = m * (x - x1)
-        // c - y1 = m * (0 - x1)
-        let c = point.y - m * point.x;
+    /** The line through two points, going on forever both ways */
+    pub fn through(point1: Point2, point2: Point2) -> Self {
         Line {
-            point1: pt2(0., c),
-            point2: point,
+            point1,
+            point2,
+            extent: Extent::Line,
         }
     }
 
-    pub fn gradient(&self) -> f32 {
-        // m = (y2 - y1) / (x2 - x1)
-        (self.point2.y - self.point1.y) / (self.point2.x - self.point1.x)
+    pub fn ray(origin: Point2, through: Point2) -> Self {
+        Line {
+            point1: origin,
+            point2: through,
+            extent: Extent::Ray,
+        }
     }
 
-    pub fn y_intercept(&self) -> f32 {
+    pub fn segment(start: Point2, end: Point2) -> Self {
+        Line {
+            point1: start,
+            point2: end,
+            extent: Extent::Segment,
+        }
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        self.point2 - self.point1
+    }
+
+    pub fn is_degenerate(&self) -> bool {
+        self.direction().length() < EPSILON
+    }
+
+    pub fn point_at(&self, t: f32) -> Point2 {
+        self.point1 + self.direction() * t
+    }
+
+    /** The range of t that is on the line */
+    fn range(&self) -> (f32, f32) {
+        match self.extent {
+            Extent::Line => (f32::NEG_INFINITY, f32::INFINITY),
+            Extent::Ray => (0., f32::INFINITY),
+            Extent::Segment => (0., 1.),
+        }
+    }
+
+    /** How far along the line it runs through a point is when closest to it, as a t */
+    fn parameter_of(&self, point: Point2) -> f32 {
+        let direction = self.direction();
+        let length_squared = direction.length_squared();
+        if length_squared == 0. {
+            return 0.;
+        }
+        (point - self.point1).dot(direction) / length_squared
+    }
+
+    /** Whether t is on the line, allowing for EPSILON either side of its ends */
+    fn contains_parameter(&self, t: f32) -> bool {
+        let slack = EPSILON / self.direction().length().max(EPSILON);
+        let (start, end) = self.range();
+        t >= start - slack && t <= end + slack
+    }
+
+    /** The rise over run of the line, None if it is vertical */
+    pub fn gradient(&self) -> Option<f32> {
+        let direction = self.direction();
+        (direction.x.abs() >= EPSILON).then(|| direction.y / direction.x)
+    }
+
+    /** Where the line through the points crosses the y axis, None if it is vertical */
+    pub fn y_intercept(&self) -> Option<f32> {
         // y = mx + c, c = y - mx
-        self.point1.y - self.gradient() * self.point1.x
+        self.gradient().map(|m| self.point1.y - m * self.point1.x)
+    }
+
+    /** The point on the line closest to point */
+    pub fn closest_point(&self, point: Point2) -> Point2 {
+        let (start, end) = self.range();
+        self.point_at(self.parameter_of(point).clamp(start, end))
+    }
+
+    pub fn distance_to_point(&self, point: Point2) -> f32 {
+        self.closest_point(point).distance(point)
+    }
+
+    /** Whether a point is on the line, to within EPSILON */
+    pub fn contains(&self, point: Point2) -> bool {
+        self.distance_to_point(point) < EPSILON
+    }
+
+    /** Where two lines meet, allowing for EPSILON in where they are */
+    pub fn intersection(&self, other: &Line) -> Intersection {
+        match (self.is_degenerate(), other.is_degenerate()) {
+            (true, _) if other.contains(self.point1) => return Intersection::Point(self.point1),
+            (_, true) if self.contains(other.point1) => return Intersection::Point(other.point1),
+            (true, _) | (_, true) => return Intersection::None,
+            (false, false) => (),
+        }
+
+        let direction = self.direction();
+        let other_direction = other.direction();
+        let offset = other.point1 - self.point1;
+        let denominator = direction.perp_dot(other_direction);
+        // How far the shorter line strays sideways from the longer one over its length
+        if (denominator / direction.length().max(other_direction.length())).abs() < EPSILON {
+            return self.overlap(other);
+        }
+
+        let t = offset.perp_dot(other_direction) / denominator;
+        let s = offset.perp_dot(direction) / denominator;
+        if self.contains_parameter(t) && other.contains_parameter(s) {
+            Intersection::Point(self.point_at(t))
+        } else {
+            Intersection::None
+        }
     }
 
-    fn intersection_with_line(&self, segment: Line) -> LineIntersection {
-        let m = [self.gradient(), segment.gradient()];
-        let c = [self.y_intercept(), segment.y_intercept()];
+    /** Where a parallel line overlaps this one, as a range of t on this one */
+    fn overlap(&self, other: &Line) -> Intersection {
+        let through = Line::through(self.point1, self.point2);
+        let other_through = Line::through(other.point1, other.point2);
+        if !through.contains(other.point1) || !other_through.contains(self.point1) {
+            return Intersection::None;
+        }
+
+        // Where the other line's range of t falls on this one
+        let (s_start, s_end) = other.range();
+        let t1 = self.parameter_of(other.point1);
+        let t2 = self.parameter_of(other.point2);
+        let at = |s: f32| {
+            if s.is_infinite() {
+                s * (t2 - t1).signum()
+            } else {
+                t1 + (t2 - t1) * s
+            }
+        };
+        let (a, b) = (at(s_start), at(s_end));
+        let (own_start, own_end) = self.range();
+        let start = own_start.max(a.min(b));
+        let end = own_end.min(a.max(b));
 
-        // Check if parallel
-        match (m[0] == m[1], c[0] == c[1]) {
-            (true, true) => return LineIntersection::Coincident,
-            (true, false) => return LineIntersection::Parallel,
-            (false, true) => return LineIntersection::Point(pt2(0., c[0])),
-            (false, false) => {}
+        let slack = EPSILON / self.direction().length();
+        if start > end + slack {
+            Intersection::None
+        } else if end - start <= slack {
+            Intersection::Point(self.point_at((start + end) / 2.))
+        } else {
+            Intersection::Overlap(match (start.is_finite(), end.is_finite()) {
+                (true, true) => Line::segment(self.point_at(start), self.point_at(end)),
+                (true, false) => Line::ray(self.point_at(start), self.point_at(start + 1.)),
+                (false, true) => Line::ray(self.point_at(end), self.point_at(end - 1.)),
+                (false, false) => through,
+            })
         }
+    }
 
-        // Find intersection
-        let x = (c[1] - c[0]) / (m[0] - m[1]);
-        let y = m[0] * x + c[0];
+    /**
+    The mirror image of a point in the line through the two points.
+    A line that is a single point reflects through that point instead.
+    */
+    pub fn reflect_point(&self, point: Point2) -> Point2 {
+        if self.is_degenerate() {
+            return self.point1 * 2. - point;
+        }
+        let foot = Line::through(self.point1, self.point2).closest_point(point);
+        foot * 2. - point
+    }
+}
 
-        return LineIntersection::Point(vec2(x, y));
+/**
+An affine transform of the plane, as a 3x3 matrix acting on points with a third coordinate of 1.
+Transforms combine with then, so a stack of them can be applied to a shape all at once.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2D {
+    pub matrix: Mat3,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Transform2D::IDENTITY
     }
+}
+
+impl Transform2D {
+    pub const IDENTITY: Self = Transform2D {
+        matrix: Mat3::IDENTITY,
+    };
 
-    fn reflect_point(&self, point: Point2) -> Point2 {
-        let m = -1. / self.gradient();
-        let line = Line::from_point_gradient(point, m);
-        let intersection = self.intersection_with_line(line);
+    pub fn translate(translation: Vec2) -> Self {
+        Transform2D {
+            matrix: Mat3::from_translation(translation),
+        }
+    }
 
-        match intersection {
-            LineIntersection::Point(p) => pt2(
-                point.x + 2.0 * (p.x - point.x),
-                point.y + 2.0 * (p.y - point.y),
+    /** Rotate around the origin the same way as rotate_point, angle is in radians */
+    pub fn rotate(angle: f32) -> Self {
+        Transform2D {
+            matrix: Mat3::from_angle(angle),
+        }
+    }
+
+    pub fn rotate_around(centre: Point2, angle: f32) -> Self {
+        Transform2D::rotate(angle).around(centre)
+    }
+
+    /** Scale away from the origin, by a different amount along each axis */
+    pub fn scale(scale: Vec2) -> Self {
+        Transform2D {
+            matrix: Mat3::from_scale(scale),
+        }
+    }
+
+    pub fn scale_around(centre: Point2, scale: Vec2) -> Self {
+        Transform2D::scale(scale).around(centre)
+    }
+
+    /** Slide x along by x_by_y times y, and y along by y_by_x times x */
+    pub fn shear(x_by_y: f32, y_by_x: f32) -> Self {
+        Transform2D {
+            matrix: Mat3::from_cols(vec3(1., y_by_x, 0.), vec3(x_by_y, 1., 0.), vec3(0., 0., 1.)),
+        }
+    }
+
+    /**
+    Mirror in the line through the axis's two points, whatever its extent.
+    An axis that is a single point reflects through that point instead, like Line::reflect_point.
+    */
+    pub fn reflect(axis: &Line) -> Self {
+        if axis.is_degenerate() {
+            return Transform2D::scale_around(axis.point1, vec2(-1., -1.));
+        }
+        let d = axis.direction().normalize();
+        let mirror = Transform2D {
+            matrix: Mat3::from_cols(
+                vec3(2. * d.x * d.x - 1., 2. * d.x * d.y, 0.),
+                vec3(2. * d.x * d.y, 2. * d.y * d.y - 1., 0.),
+                vec3(0., 0., 1.),
             ),
-            _ => panic!(),
+        };
+        mirror.around(axis.point1)
+    }
+
+    /** The same transform, done with centre where the origin was */
+    fn around(self, centre: Point2) -> Self {
+        Transform2D::translate(-centre)
+            .then(self)
+            .then(Transform2D::translate(centre))
+    }
+
+    /** This transform followed by next */
+    pub fn then(self, next: Transform2D) -> Self {
+        Transform2D {
+            matrix: next.matrix * self.matrix,
+        }
+    }
+
+    /** The transform that undoes this one, None if this one flattens the plane onto a line or point */
+    pub fn inverse(&self) -> Option<Self> {
+        if self.matrix.determinant() == 0. {
+            return None;
+        }
+        let matrix = self.matrix.inverse();
+        matrix.is_finite().then_some(Transform2D { matrix })
+    }
+
+    pub fn apply(&self, point: Point2) -> Point2 {
+        self.matrix.transform_point2(point)
+    }
+
+    /** Transform a direction or offset, which translation doesn't move */
+    pub fn apply_to_vector(&self, vector: Vec2) -> Vec2 {
+        self.matrix.transform_vector2(vector)
+    }
+
+    /** Transform every point of a polyline in place */
+    pub fn apply_to_points(&self, points: &mut [Point2]) {
+        for point in points {
+            *point = self.apply(*point);
         }
     }
 }
@@ -80,6 +339,134 @@ pub enum Direction {
     Left,
 }
 
+/** Which way round a polygon's vertices go, with y going up */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    Counterclockwise,
+}
+
+/** Why a polygon can't be used as a shape */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolygonError {
+    /** Fewer than 3 vertices, with how many there are */
+    TooFewVertices(usize),
+    /** A coordinate that is NaN or infinite, at a vertex */
+    NotFinite(usize),
+    /** Two vertices within EPSILON of each other */
+    DuplicatePoint(usize, usize),
+    /** All the vertices are on a line */
+    ZeroArea,
+    /** A star whose step doesn't go round every vertex once */
+    InvalidStar { points: usize, step: usize },
+    /** SVG that couldn't be read, with why */
+    InvalidSvg(String),
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolygonError::TooFewVertices(count) => {
+                write!(f, "a polygon needs at least 3 vertices, not {count}")
+            }
+            PolygonError::NotFinite(index) => write!(f, "vertex {index} is not a finite point"),
+            PolygonError::DuplicatePoint(first, second) => {
+                write!(f, "vertices {first} and {second} are in the same place")
+            }
+            PolygonError::ZeroArea => write!(f, "the vertices are all on one line"),
+            PolygonError::InvalidStar { points, step } => write!(
+                f,
+                "a star with {points} points can't be made by joining points {step} apart"
+            ),
+            PolygonError::InvalidSvg(reason) => write!(f, "invalid SVG: {reason}"),
+        }
+    }
+}
+
+impl Error for PolygonError {}
+
+/** How offset polygons go round the corners that open up as the sides move apart */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Join {
+    /** Extend the sides until they meet, or cut the corner off if that is past MITRE_LIMIT */
+    Mitre,
+    Round,
+}
+
+/** How many times the offset distance a mitred corner can stick out before it is cut off, as in SVG */
+pub const MITRE_LIMIT: f32 = 4.;
+
+/** Sides of the polygons standing in for circles at round joins */
+const ROUND_JOIN_SIDES: usize = 32;
+
+/**
+A shape that boolean operations, offsetting or Minkowski sums left, which can have holes.
+The exterior goes counterclockwise and the holes clockwise.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub exterior: Polygon,
+    pub holes: Vec<Polygon>,
+}
+
+impl Region {
+    fn from_geo(polygon: &geo::Polygon<f32>) -> Self {
+        let ring = |line_string: &LineString<f32>, winding| {
+            let mut points: Vec<Point2> = line_string.coords().map(|c| pt2(c.x, c.y)).collect();
+            // geo closes rings by repeating the first point at the end
+            if points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            let mut polygon = Polygon { points };
+            polygon.normalise_winding(winding);
+            polygon
+        };
+        Region {
+            exterior: ring(polygon.exterior(), Winding::Counterclockwise),
+            holes: polygon
+                .interiors()
+                .iter()
+                .map(|hole| ring(hole, Winding::Clockwise))
+                .collect(),
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        self.exterior.area() - self.holes.iter().map(Polygon::area).sum::<f32>()
+    }
+
+    /** Whether a point is in the region or on its edge, to within EPSILON */
+    pub fn contains(&self, point: Point2) -> bool {
+        self.exterior.contains(point)
+            && !self
+                .holes
+                .iter()
+                .any(|hole| hole.contains(point) && !hole.edges().any(|edge| edge.contains(point)))
+    }
+}
+
+fn regions(multi_polygon: &MultiPolygon<f32>) -> Vec<Region> {
+    multi_polygon.iter().map(Region::from_geo).collect()
+}
+
+/**
+The union of many shapes, merged in pairs so that each merge is between shapes of similar size.
+geo treats overlapping polygons in one MultiPolygon as cancelling out, so they can't be merged at once.
+*/
+fn union_all(mut shapes: Vec<MultiPolygon<f32>>) -> MultiPolygon<f32> {
+    while shapes.len() > 1 {
+        shapes = shapes
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => a.union(b),
+                [a] => a.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    shapes.pop().unwrap_or_else(|| MultiPolygon::new(vec![]))
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Polygon {
     pub points: Vec<Point2>,
@@ -103,12 +490,166 @@ impl Polygon {
         Polygon { points }
     }
 
-    /** Align self to be above, below, or to the right or left of polygon */
+    /**
+    The star polygon {points/step}, the vertices of a regular polygon like new's
+    each joined to the one step vertices further round.
+    Step has to have no factors in common with points, or the lines would make several polygons.
+    */
+    pub fn star(radius: f32, points: usize, step: usize) -> Result<Self, PolygonError> {
+        if points < 3 || step == 0 || step >= points || gcd(points, step) != 1 {
+            return Err(PolygonError::InvalidStar { points, step });
+        }
+        let regular = Polygon::new(radius, points);
+        Ok(Polygon {
+            points: (0..points)
+                .map(|index| regular.points[index * step % points])
+                .collect(),
+        })
+    }
+
+    /** Centred on the origin, going counterclockwise from the bottom left */
+    pub fn rectangle(width: f32, height: f32) -> Self {
+        let (x, y) = (width / 2., height / 2.);
+        Polygon {
+            points: vec![pt2(-x, -y), pt2(x, -y), pt2(x, y), pt2(-x, y)],
+        }
+    }
+
+    /**
+    Centred on the origin, with the first side along the x axis
+    and angle in radians between it and the last side
+    */
+    pub fn rhombus(side: f32, angle: f32) -> Self {
+        let along = vec2(side, 0.);
+        let up = vec2(angle.cos(), angle.sin()) * side;
+        let corner = -(along + up) / 2.;
+        Polygon {
+            points: vec![corner, corner + along, corner + along + up, corner + up],
+        }
+    }
+
+    /** A polygon with the given vertices, if they make one that has an inside */
+    pub fn from_vertices(points: Vec<Point2>) -> Result<Self, PolygonError> {
+        let polygon = Polygon { points };
+        polygon.validate()?;
+        Ok(polygon)
+    }
+
+    /**
+    Read the points attribute of an SVG polygon, like "0,0 10,0 10,10".
+    Coordinates are taken as they are, the same as svg::to_svg writes them,
+    so shapes from documents with y going down come out upside down.
+    */
+    pub fn from_svg_points(attribute: &str) -> Result<Self, PolygonError> {
+        let numbers = svg_tokens(attribute)?
+            .into_iter()
+            .map(|token| match token {
+                SvgToken::Number(number) => Ok(number),
+                SvgToken::Command(command) => Err(PolygonError::InvalidSvg(format!(
+                    "unexpected {command} in points"
+                ))),
+            })
+            .collect::<Result<Vec<f32>, _>>()?;
+        if numbers.len() % 2 == 1 {
+            return Err(PolygonError::InvalidSvg(
+                "points have an x without a y".to_string(),
+            ));
+        }
+        Polygon::from_vertices(
+            numbers
+                .chunks(2)
+                .map(|pair| pt2(pair[0], pair[1]))
+                .collect(),
+        )
+    }
+
+    /**
+    Read the d attribute of an SVG path made of straight lines, with a polygon for each subpath.
+    Curves and arcs aren't supported, and coordinates are taken as they are like in from_svg_points.
+    */
+    pub fn from_svg_path(data: &str) -> Result<Vec<Self>, PolygonError> {
+        let tokens = svg_tokens(data)?;
+        if !matches!(tokens.first(), None | Some(SvgToken::Command('M' | 'm'))) {
+            return Err(PolygonError::InvalidSvg(
+                "paths have to start with a move".to_string(),
+            ));
+        }
+        let mut tokens = tokens.into_iter();
+        let mut polygons = vec![];
+        let mut subpath: Vec<Point2> = vec![];
+        let mut position = Vec2::ZERO;
+        let mut start = Vec2::ZERO;
+        let mut command = None;
+
+        let mut finish = |subpath: &mut Vec<Point2>| -> Result<(), PolygonError> {
+            // Subpaths are often closed by going back to the start as well as with Z
+            if subpath.len() > 1 && subpath.first() == subpath.last() {
+                subpath.pop();
+            }
+            if !subpath.is_empty() {
+                polygons.push(Polygon::from_vertices(std::mem::take(subpath))?);
+            }
+            Ok(())
+        };
+
+        while let Some(token) = tokens.next() {
+            let first = match token {
+                SvgToken::Command('Z' | 'z') => {
+                    finish(&mut subpath)?;
+                    position = start;
+                    command = None;
+                    continue;
+                }
+                SvgToken::Command(letter @ ('M' | 'm' | 'L' | 'l' | 'H' | 'h' | 'V' | 'v')) => {
+                    command = Some(letter);
+                    continue;
+                }
+                SvgToken::Command(letter) => {
+                    return Err(PolygonError::InvalidSvg(format!(
+                        "only straight lines are supported, not {letter}"
+                    )))
+                }
+                SvgToken::Number(number) => number,
+            };
+            let Some(letter) = command else {
+                return Err(PolygonError::InvalidSvg(format!(
+                    "{first} has no command before it"
+                )));
+            };
+            let mut second = || match tokens.next() {
+                Some(SvgToken::Number(number)) => Ok(number),
+                _ => Err(PolygonError::InvalidSvg(format!(
+                    "{letter} needs two coordinates"
+                ))),
+            };
+            let relative = if letter.is_ascii_lowercase() {
+                position
+            } else {
+                Vec2::ZERO
+            };
+            position = match letter.to_ascii_uppercase() {
+                'H' => pt2(first + relative.x, position.y),
+                'V' => pt2(position.x, first + relative.y),
+                _ => pt2(first, second()?) + relative,
+            };
+            if letter.eq_ignore_ascii_case(&'M') {
+                finish(&mut subpath)?;
+                start = position;
+                // Coordinates after a move's first pair are lines
+                command = Some(if letter == 'M' { 'L' } else { 'l' });
+            }
+            subpath.push(position);
+        }
+        finish(&mut subpath)?;
+        Ok(polygons)
+    }
+
+    /** Align self to be above, below, or to the right or left of polygon, if both have vertices */
     pub fn align(&mut self, polygon: &Polygon, direction: Direction) {
-        let bounding_boxes = [
-            bounding_rect(self.points.clone()).unwrap(),
-            bounding_rect(polygon.points.clone()).unwrap(),
-        ];
+        let (Some(own), Some(other)) = (self.bounding_rect(), polygon.bounding_rect()) else {
+            return;
+        };
+        let bounding_boxes = [own, other];
 
         match direction {
             Direction::Above => {
@@ -142,61 +683,415 @@ impl Polygon {
         }
     }
 
+    /** Apply a transform to every vertex */
+    pub fn transform(&mut self, transform: &Transform2D) {
+        transform.apply_to_points(&mut self.points);
+    }
+
+    pub fn transformed(&self, transform: &Transform2D) -> Self {
+        let mut polygon = self.clone();
+        polygon.transform(transform);
+        polygon
+    }
+
     pub fn translate(&mut self, translation: Vec2) {
-        for point in &mut self.points {
-            point.x += translation.x;
-            point.y += translation.y;
-        }
+        self.transform(&Transform2D::translate(translation));
     }
 
     pub fn reflect(&mut self, axis: Line) {
-        for point in &mut self.points {
-            *point = axis.reflect_point(*point);
-        }
+        self.transform(&Transform2D::reflect(&axis));
     }
 
-    /** Rotate clockwise around centroid */
+    /** Rotate clockwise around centroid, which does nothing to a polygon with no vertices */
     pub fn rotate(&mut self, angle: f32) {
-        let centre = centroid(self.points.clone()).unwrap();
-        self.rotate_around_point(centre, angle);
+        if let Some(centre) = self.centroid() {
+            self.rotate_around_point(centre, angle);
+        }
     }
 
     /** Rotate clockwise, angle is in radians */
     pub fn rotate_around_point(&mut self, centre: Point2, angle: f32) {
-        for point in &mut self.points {
-            rotate_point(point, centre, angle.sin(), angle.cos());
-        }
+        self.transform(&Transform2D::rotate_around(centre, angle));
     }
 
+    /** Scale around centroid, which does nothing to a polygon with no vertices */
     pub fn dilate(&mut self, scale: f32) {
-        self.dilate_from_point(scale, centroid(self.points.clone()).unwrap());
+        if let Some(centre) = self.centroid() {
+            self.dilate_from_point(scale, centre);
+        }
     }
 
     pub fn dilate_from_point(&mut self, scale: f32, centre: Point2) {
-        for point in &mut self.points {
-            let dilated = pt2(point.x - centre.x, point.y - centre.y) * scale;
+        self.transform(&Transform2D::scale_around(centre, vec2(scale, scale)));
+    }
 
-            // Translate back
-            *point = pt2(dilated.x + centre.x, dilated.y + centre.y);
+    /** How far the point is from the polygon's edge, or 0 if it's on it, None if it has no vertices */
+    pub fn distance_to_point(&self, point: Point2) -> Option<f32> {
+        let closest = self
+            .to_geo()
+            .closest_point(&geo::point! {x: point.x, y: point.y});
+        match closest {
+            geo::Closest::SinglePoint(p) => Some(pt2(p.x(), p.y()).distance(point)),
+            geo::Closest::Intersection(_) => Some(0.),
+            geo::Closest::Indeterminate => None,
         }
     }
 
-    pub fn distance_to_point(&self, point: Point2) -> f32 {
+    /** The centre of mass of the vertices, None if there are none */
+    pub fn centroid(&self) -> Option<Point2> {
+        centroid(self.points.iter().copied())
+    }
+
+    pub fn bounding_rect(&self) -> Option<Rect> {
+        bounding_rect(self.points.iter().copied())
+    }
+
+    /** Each side as a segment, from each vertex to the next and from the last back to the first */
+    pub fn edges(&self) -> impl Iterator<Item = Line> + '_ {
+        let next = self.points.iter().cycle().skip(1);
+        self.points
+            .iter()
+            .zip(next)
+            .map(|(start, end)| Line::segment(*start, *end))
+    }
+
+    /** Check that the polygon has an inside, for shapes that come from users */
+    pub fn validate(&self) -> Result<(), PolygonError> {
+        if self.points.len() < 3 {
+            return Err(PolygonError::TooFewVertices(self.points.len()));
+        }
+        if let Some(index) = self.points.iter().position(|point| !point.is_finite()) {
+            return Err(PolygonError::NotFinite(index));
+        }
+        for (first, point) in self.points.iter().enumerate() {
+            let duplicate = self.points[first + 1..]
+                .iter()
+                .position(|other| point.distance(*other) < EPSILON);
+            if let Some(offset) = duplicate {
+                return Err(PolygonError::DuplicatePoint(first, first + 1 + offset));
+            }
+        }
+        if self.winding().is_none() {
+            return Err(PolygonError::ZeroArea);
+        }
+        Ok(())
+    }
+
+    /** The area, positive if the vertices go counterclockwise and negative if they go clockwise */
+    pub fn signed_area(&self) -> f32 {
+        // Shoelace formula
+        self.edges()
+            .map(|edge| edge.point1.perp_dot(edge.point2))
+            .sum::<f32>()
+            / 2.
+    }
+
+    /** The area enclosed, counting parts that are gone round twice twice */
+    pub fn area(&self) -> f32 {
+        self.signed_area().abs()
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        self.edges().map(|edge| edge.direction().length()).sum()
+    }
+
+    /** None if the polygon has no area to go round */
+    pub fn winding(&self) -> Option<Winding> {
+        let area = self.signed_area();
+        if area.abs() < EPSILON * EPSILON {
+            None
+        } else if area > 0. {
+            Some(Winding::Counterclockwise)
+        } else {
+            Some(Winding::Clockwise)
+        }
+    }
+
+    /** Reverse the vertices if they go the other way, so that shapes from anywhere can be compared */
+    pub fn normalise_winding(&mut self, winding: Winding) {
+        if self.winding().is_some_and(|current| current != winding) {
+            self.points.reverse();
+        }
+    }
+
+    /** Every vertex turns the same way, and the sides only meet at their ends */
+    pub fn is_convex(&self) -> bool {
+        let count = self.points.len();
+        if count < 3 {
+            return false;
+        }
+        let turns = (0..count).map(|index| {
+            let [previous, vertex, next] =
+                [count - 1, 0, 1].map(|offset| self.points[(index + offset) % count]);
+            (vertex - previous).perp_dot(next - vertex)
+        });
+        let (mut left, mut right) = (false, false);
+        for turn in turns {
+            left |= turn > EPSILON * EPSILON;
+            right |= turn < -EPSILON * EPSILON;
+        }
+        !(left && right) && self.is_simple()
+    }
+
+    /**
+    Pairs of sides that cross or touch other than where neighbouring sides share a vertex,
+    by the index of the vertex each side starts at
+    */
+    pub fn self_intersections(&self) -> Vec<(usize, usize)> {
+        let edges: Vec<Line> = self.edges().collect();
+        let count = edges.len();
+        let mut intersections = vec![];
+        for first in 0..count {
+            for second in first + 1..count {
+                let intersection = edges[first].intersection(&edges[second]);
+                let neighbours = second == first + 1 || (first == 0 && second == count - 1);
+                let crosses = match intersection {
+                    Intersection::None => false,
+                    // Neighbours always meet at their shared vertex, but shouldn't fold back onto each other
+                    Intersection::Point(_) => !neighbours,
+                    Intersection::Overlap(_) => true,
+                };
+                if crosses {
+                    intersections.push((first, second));
+                }
+            }
+        }
+        intersections
+    }
+
+    pub fn is_simple(&self) -> bool {
+        self.self_intersections().is_empty()
+    }
+
+    /**
+    Whether a point is inside the polygon or on its edge, to within EPSILON.
+    Parts of self-intersecting polygons that are gone round twice are outside,
+    the same as when they are drawn.
+    */
+    pub fn contains(&self, point: Point2) -> bool {
+        if self.edges().any(|edge| edge.contains(point)) {
+            return true;
+        }
+        // Count the sides that a ray going right from the point crosses
+        self.edges()
+            .filter(|edge| {
+                let (a, b) = (edge.point1, edge.point2);
+                (a.y > point.y) != (b.y > point.y)
+                    && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            })
+            .count()
+            % 2
+            == 1
+    }
+
+    fn to_geo(&self) -> geo::Polygon<f32> {
         let points: Vec<Coord<f32>> = self
             .points
             .iter()
             .map(|p| geo::coord! {x: p.x, y: p.y})
             .collect();
-        let polygon = geo::Polygon::new(LineString::from(points), vec![]);
-        let closest = polygon.closest_point(&geo::point! {x: point.x, y: point.y});
-        match closest {
-            geo::Closest::SinglePoint(p) => pt2(p.x(), p.y()).distance(point),
-            geo::Closest::Intersection(_) => 0.,
-            geo::Closest::Indeterminate => panic!("Unable to determine closest point"),
+        geo::Polygon::new(LineString::from(points), vec![])
+    }
+
+    pub fn union(&self, other: &Polygon) -> Vec<Region> {
+        regions(&self.to_geo().union(&other.to_geo()))
+    }
+
+    pub fn intersection(&self, other: &Polygon) -> Vec<Region> {
+        regions(&self.to_geo().intersection(&other.to_geo()))
+    }
+
+    /** What is left of self with other cut out of it */
+    pub fn difference(&self, other: &Polygon) -> Vec<Region> {
+        regions(&self.to_geo().difference(&other.to_geo()))
+    }
+
+    /** What is in one polygon or the other but not both */
+    pub fn xor(&self, other: &Polygon) -> Vec<Region> {
+        regions(&self.to_geo().xor(&other.to_geo()))
+    }
+
+    /**
+    Grow the polygon by distance all round, or shrink it if distance is negative,
+    which can split it into several regions or leave nothing.
+    Assumes that the polygon doesn't cross itself.
+    */
+    pub fn offset(&self, distance: f32, join: Join) -> Vec<Region> {
+        let mut polygon = self.clone();
+        polygon.normalise_winding(Winding::Counterclockwise);
+        if polygon.winding().is_none() {
+            return vec![];
+        }
+        if distance.abs() < EPSILON {
+            return vec![Region::from_geo(&polygon.to_geo())];
+        }
+        let side = distance.signum();
+        let width = distance.abs();
+        // Going counterclockwise, the outside is to the right of each side
+        let normal = |edge: &Line| {
+            let direction = edge.direction().normalize_or_zero();
+            vec2(direction.y, -direction.x) * side
+        };
+
+        // Every side swept both ways by the distance, with the corners that open up filled in
+        let edges: Vec<Line> = polygon.edges().collect();
+        let mut pieces = vec![];
+        for edge in &edges {
+            let offset = normal(edge) * width;
+            pieces.push(Polygon {
+                points: vec![
+                    edge.point1 - offset,
+                    edge.point2 - offset,
+                    edge.point2 + offset,
+                    edge.point1 + offset,
+                ],
+            });
+        }
+        for (index, vertex) in polygon.points.iter().enumerate() {
+            let previous = &edges[(index + edges.len() - 1) % edges.len()];
+            let next = &edges[index];
+            // Corners only open up on the side that the polygon turns away from
+            if previous.direction().perp_dot(next.direction()) * side <= 0. {
+                continue;
+            }
+            let (before, after) = (normal(previous), normal(next));
+            let points = match join {
+                Join::Round => {
+                    let mut circle = Polygon::new(width, ROUND_JOIN_SIDES);
+                    circle.translate(*vertex);
+                    circle.points
+                }
+                Join::Mitre => {
+                    let bisector = before + after;
+                    let ratio = 2. / bisector.length();
+                    let mut points = vec![*vertex, *vertex + before * width];
+                    if ratio <= MITRE_LIMIT {
+                        points.push(*vertex + bisector * width / (1. + before.dot(after)));
+                    }
+                    points.push(*vertex + after * width);
+                    points
+                }
+            };
+            pieces.push(Polygon { points });
+        }
+
+        let band = union_all(
+            pieces
+                .iter()
+                .filter(|piece| piece.winding().is_some())
+                .map(|piece| MultiPolygon::new(vec![piece.to_geo()]))
+                .collect(),
+        );
+        let polygon = polygon.to_geo();
+        regions(&if side > 0. {
+            polygon.union(&band)
+        } else {
+            polygon.difference(&band)
+        })
+    }
+
+    /**
+    Every point of self added to every point of other, which is how far other
+    sweeps if it is moved with its origin kept on self.
+    Assumes that neither polygon crosses itself.
+    */
+    pub fn minkowski_sum(&self, other: &Polygon) -> Vec<Region> {
+        let (Some(&first), Some(&other_first)) = (self.points.first(), other.points.first()) else {
+            return vec![];
+        };
+        // The boundary of the sum is made of sums of sides, and the rest of it is covered by
+        // one polygon moved by a point of the other
+        let mut pieces = vec![
+            self.transformed(&Transform2D::translate(other_first)),
+            other.transformed(&Transform2D::translate(first)),
+        ];
+        for edge in self.edges() {
+            for other_edge in other.edges() {
+                pieces.push(Polygon {
+                    points: vec![
+                        edge.point1 + other_edge.point1,
+                        edge.point2 + other_edge.point1,
+                        edge.point2 + other_edge.point2,
+                        edge.point1 + other_edge.point2,
+                    ],
+                });
+            }
         }
+        regions(&union_all(
+            pieces
+                .iter()
+                .filter(|piece| piece.winding().is_some())
+                .map(|piece| MultiPolygon::new(vec![piece.to_geo()]))
+                .collect(),
+        ))
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
 }
 
+enum SvgToken {
+    Command(char),
+    Number(f32),
+}
+
+/**
+Split SVG coordinates into command letters and numbers, which can be separated by
+spaces, commas, or nothing at all where a sign or second decimal point starts the next number
+*/
+fn svg_tokens(text: &str) -> Result<Vec<SvgToken>, PolygonError> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() || c == ',' {
+            chars.next();
+        } else if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            tokens.push(SvgToken::Command(c));
+            chars.next();
+        } else {
+            let mut end = start;
+            let mut seen_point = false;
+            let mut seen_exponent = false;
+            let mut previous = None;
+            while let Some(&(index, c)) = chars.peek() {
+                let sign_allowed = index == start || matches!(previous, Some('e' | 'E'));
+                let continues = c.is_ascii_digit()
+                    || ((c == '+' || c == '-') && sign_allowed)
+                    || (c == '.' && !seen_point && !seen_exponent)
+                    || ((c == 'e' || c == 'E') && !seen_exponent && index != start);
+                if !continues {
+                    break;
+                }
+                seen_point |= c == '.';
+                seen_exponent |= c == 'e' || c == 'E';
+                previous = Some(c);
+                end = index + c.len_utf8();
+                chars.next();
+            }
+            let number = &text[start..end];
+            match number.parse() {
+                Ok(number) => tokens.push(SvgToken::Number(number)),
+                Err(_) => {
+                    let number = if number.is_empty() {
+                        &text[start..start + c.len_utf8()]
+                    } else {
+                        number
+                    };
+                    return Err(PolygonError::InvalidSvg(format!(
+                        "{number} is not a number"
+                    )));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
 /**
 Assumes that b is the centre point, angle is in radians
 */
@@ -271,3 +1166,554 @@ impl Mul<u32> for PointU32 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn assert_close(a: Point2, b: Point2) {
+        assert!(a.distance(b) < EPSILON, "{a} is not {b}");
+    }
+
+    fn point(i: Intersection) -> Point2 {
+        match i {
+            Intersection::Point(point) => point,
+            other => panic!("expected a point, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn vertical_lines_have_no_gradient() {
+        let line = Line::through(pt2(2., 0.), pt2(2., 5.));
+        assert_eq!(line.gradient(), None);
+        assert_eq!(line.y_intercept(), None);
+        let line = Line::through(pt2(0., 1.), pt2(2., 5.));
+        assert_eq!(line.gradient(), Some(2.));
+        assert_eq!(line.y_intercept(), Some(1.));
+    }
+
+    #[test]
+    fn vertical_and_horizontal_lines_meet() {
+        let vertical = Line::through(pt2(3., 0.), pt2(3., 1.));
+        let horizontal = Line::through(pt2(0., -2.), pt2(1., -2.));
+        assert_close(point(vertical.intersection(&horizontal)), pt2(3., -2.));
+    }
+
+    #[test]
+    fn parallel_lines_do_not_meet() {
+        let a = Line::through(pt2(0., 0.), pt2(0., 1.));
+        let b = Line::through(pt2(1., 0.), pt2(1., 1.));
+        assert_eq!(a.intersection(&b), Intersection::None);
+    }
+
+    #[test]
+    fn segments_that_would_cross_as_lines_do_not_meet() {
+        let a = Line::segment(pt2(0., 0.), pt2(1., 1.));
+        let b = Line::segment(pt2(3., 0.), pt2(2., 1.));
+        assert_eq!(a.intersection(&b), Intersection::None);
+        let ray = Line::ray(pt2(3., 0.), pt2(2., 1.));
+        let line = Line::through(a.point1, a.point2);
+        assert_close(point(line.intersection(&ray)), pt2(1.5, 1.5));
+    }
+
+    #[test]
+    fn segments_meet_at_their_ends() {
+        let a = Line::segment(pt2(0., 0.), pt2(1., 0.));
+        let b = Line::segment(pt2(1., 0.), pt2(1., 1.));
+        assert_close(point(a.intersection(&b)), pt2(1., 0.));
+        let c = Line::segment(pt2(1. + EPSILON / 2., 0.), pt2(2., 0.));
+        assert_close(point(a.intersection(&c)), pt2(1., 0.));
+    }
+
+    #[test]
+    fn collinear_segments_overlap() {
+        let a = Line::segment(pt2(0., 0.), pt2(0., 4.));
+        let b = Line::segment(pt2(0., 6.), pt2(0., 2.));
+        assert_eq!(
+            a.intersection(&b),
+            Intersection::Overlap(Line::segment(pt2(0., 2.), pt2(0., 4.)))
+        );
+        let c = Line::segment(pt2(0., 5.), pt2(0., 6.));
+        assert_eq!(a.intersection(&c), Intersection::None);
+    }
+
+    #[test]
+    fn collinear_rays_overlap() {
+        let a = Line::ray(pt2(0., 0.), pt2(1., 0.));
+        let same_way = Line::ray(pt2(2., 0.), pt2(3., 0.));
+        let Intersection::Overlap(overlap) = a.intersection(&same_way) else {
+            panic!("rays going the same way should overlap");
+        };
+        assert_eq!(overlap.extent, Extent::Ray);
+        assert_close(overlap.point1, pt2(2., 0.));
+        assert!(overlap.direction().x > 0.);
+
+        let facing = Line::ray(pt2(2., 0.), pt2(1., 0.));
+        assert_eq!(
+            a.intersection(&facing),
+            Intersection::Overlap(Line::segment(pt2(0., 0.), pt2(2., 0.)))
+        );
+        let away = Line::ray(pt2(0., 0.), pt2(-1., 0.));
+        assert_close(point(a.intersection(&away)), pt2(0., 0.));
+    }
+
+    #[test]
+    fn degenerate_lines_do_not_panic() {
+        let dot = Line::segment(pt2(1., 1.), pt2(1., 1.));
+        let line = Line::through(pt2(0., 0.), pt2(2., 2.));
+        assert_close(point(dot.intersection(&line)), pt2(1., 1.));
+        assert_close(point(line.intersection(&dot)), pt2(1., 1.));
+        assert_eq!(dot.gradient(), None);
+        assert_close(dot.reflect_point(pt2(2., 3.)), pt2(0., -1.));
+    }
+
+    #[test]
+    fn reflects_in_vertical_and_horizontal_lines() {
+        let vertical = Line::through(pt2(1., 0.), pt2(1., 1.));
+        assert_close(vertical.reflect_point(pt2(3., 2.)), pt2(-1., 2.));
+        let horizontal = Line::through(pt2(0., 1.), pt2(1., 1.));
+        assert_close(horizontal.reflect_point(pt2(3., 2.)), pt2(3., 0.));
+    }
+
+    #[test]
+    fn transforms_compose_in_order() {
+        let transform = Transform2D::translate(vec2(1., 0.)).then(Transform2D::scale(vec2(2., 3.)));
+        assert_close(transform.apply(pt2(1., 1.)), pt2(4., 3.));
+        let transform = Transform2D::scale(vec2(2., 3.)).then(Transform2D::translate(vec2(1., 0.)));
+        assert_close(transform.apply(pt2(1., 1.)), pt2(3., 3.));
+    }
+
+    #[test]
+    fn rotating_around_a_point_matches_rotate_point() {
+        let (centre, angle) = (pt2(2., -1.), 0.7);
+        let mut expected = pt2(5., 4.);
+        rotate_point(&mut expected, centre, angle.sin(), angle.cos());
+        let rotated = Transform2D::rotate_around(centre, angle).apply(pt2(5., 4.));
+        assert_close(rotated, expected);
+    }
+
+    #[test]
+    fn shears_along_each_axis() {
+        assert_close(Transform2D::shear(2., 0.).apply(pt2(1., 3.)), pt2(7., 3.));
+        assert_close(Transform2D::shear(0., 2.).apply(pt2(1., 3.)), pt2(1., 5.));
+    }
+
+    #[test]
+    fn flattening_transforms_have_no_inverse() {
+        assert_eq!(Transform2D::scale(vec2(1., 0.)).inverse(), None);
+        assert_eq!(Transform2D::shear(1., 1.).inverse(), None);
+        assert_eq!(Transform2D::IDENTITY.inverse(), Some(Transform2D::IDENTITY));
+    }
+
+    #[test]
+    fn polygons_and_polylines_transform_every_point() {
+        let square = Polygon {
+            points: vec![pt2(0., 0.), pt2(1., 0.), pt2(1., 1.), pt2(0., 1.)],
+        };
+        let transform = Transform2D::reflect(&Line::through(pt2(0., 0.), pt2(0., 1.)))
+            .then(Transform2D::translate(vec2(0., 2.)));
+        let moved = square.transformed(&transform);
+        let mut polyline = square.points.clone();
+        transform.apply_to_points(&mut polyline);
+        assert_eq!(moved.points, polyline);
+        assert_close(moved.points[2], pt2(-1., 3.));
+    }
+
+    fn polygon(points: &[(f32, f32)]) -> Polygon {
+        Polygon {
+            points: points.iter().map(|&(x, y)| pt2(x, y)).collect(),
+        }
+    }
+
+    #[test]
+    fn measures_a_square() {
+        let square = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        assert_eq!(square.signed_area(), 4.);
+        assert_eq!(square.perimeter(), 8.);
+        assert_eq!(square.winding(), Some(Winding::Counterclockwise));
+        assert!(square.is_convex());
+        assert!(square.is_simple());
+        assert_eq!(square.validate(), Ok(()));
+
+        let mut clockwise = square.clone();
+        clockwise.points.reverse();
+        assert_eq!(clockwise.signed_area(), -4.);
+        clockwise.normalise_winding(Winding::Counterclockwise);
+        assert_eq!(clockwise.winding(), Some(Winding::Counterclockwise));
+    }
+
+    #[test]
+    fn concave_and_crossed_polygons() {
+        let arrow = polygon(&[(0., 0.), (4., 0.), (4., 4.), (2., 1.), (0., 4.)]);
+        assert!(!arrow.is_convex());
+        assert!(arrow.is_simple());
+
+        let bow_tie = polygon(&[(0., 0.), (2., 2.), (2., 0.), (0., 2.)]);
+        assert_eq!(bow_tie.self_intersections(), vec![(0, 2)]);
+        assert!(!bow_tie.is_convex());
+        assert_eq!(bow_tie.signed_area(), 0.);
+
+        // A pentagram turns the same way at every vertex, but isn't convex
+        let mut star = Polygon::new(10., 5);
+        star.points = [0, 2, 4, 1, 3].map(|index| star.points[index]).to_vec();
+        assert!(!star.is_convex());
+        assert_eq!(star.self_intersections().len(), 5);
+    }
+
+    #[test]
+    fn points_inside_and_outside() {
+        let arrow = polygon(&[(0., 0.), (4., 0.), (4., 4.), (2., 1.), (0., 4.)]);
+        assert!(arrow.contains(pt2(1., 1.)));
+        assert!(!arrow.contains(pt2(2., 3.)));
+        assert!(!arrow.contains(pt2(-1., 1.)));
+        assert!(arrow.contains(pt2(4., 2.)));
+        assert!(arrow.contains(pt2(0., 0.)));
+    }
+
+    #[test]
+    fn degenerate_polygons_are_errors() {
+        let line = polygon(&[(0., 0.), (1., 1.)]);
+        assert_eq!(line.validate(), Err(PolygonError::TooFewVertices(2)));
+        let repeated = polygon(&[(0., 0.), (1., 0.), (1., 1.), (1., 0.)]);
+        assert_eq!(repeated.validate(), Err(PolygonError::DuplicatePoint(1, 3)));
+        let flat = polygon(&[(0., 0.), (1., 1.), (3., 3.)]);
+        assert_eq!(flat.validate(), Err(PolygonError::ZeroArea));
+        let nan = polygon(&[(0., 0.), (1., f32::NAN), (3., 3.)]);
+        assert_eq!(nan.validate(), Err(PolygonError::NotFinite(1)));
+        assert!(Polygon::new(0., 4).validate().is_err());
+        assert_eq!(Polygon::new(10., 7).validate(), Ok(()));
+    }
+
+    #[test]
+    fn empty_polygons_are_left_alone() {
+        let square = polygon(&[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]);
+        let mut empty = Polygon { points: vec![] };
+        empty.rotate(1.);
+        empty.dilate(2.);
+        empty.align(&square, Direction::Above);
+        assert!(empty.points.is_empty());
+        assert_eq!(empty.distance_to_point(pt2(0., 0.)), None);
+
+        let mut aligned = square.clone();
+        aligned.align(&empty, Direction::Above);
+        assert_eq!(aligned, square);
+        assert_eq!(square.distance_to_point(pt2(0.5, 3.)), Some(2.));
+    }
+
+    fn total_area(regions: &[Region]) -> f32 {
+        regions.iter().map(Region::area).sum()
+    }
+
+    fn assert_area(regions: &[Region], expected: f32) {
+        let area = total_area(regions);
+        assert!(
+            (area - expected).abs() < 1e-3,
+            "area {area} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn boolean_operations_on_overlapping_squares() {
+        let a = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        let b = polygon(&[(1., 1.), (3., 1.), (3., 3.), (1., 3.)]);
+        assert_area(&a.union(&b), 7.);
+        assert_area(&a.intersection(&b), 1.);
+        assert_area(&a.difference(&b), 3.);
+        assert_area(&a.xor(&b), 6.);
+        assert_eq!(a.xor(&b).len(), 2);
+
+        let far = polygon(&[(5., 5.), (6., 5.), (6., 6.), (5., 6.)]);
+        assert!(a.intersection(&far).is_empty());
+        assert_eq!(a.union(&far).len(), 2);
+    }
+
+    #[test]
+    fn difference_can_leave_a_hole() {
+        let outer = polygon(&[(0., 0.), (4., 0.), (4., 4.), (0., 4.)]);
+        let inner = polygon(&[(1., 1.), (1., 3.), (3., 3.), (3., 1.)]);
+        let regions = outer.difference(&inner);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].holes.len(), 1);
+        assert_eq!(
+            regions[0].exterior.winding(),
+            Some(Winding::Counterclockwise)
+        );
+        assert_eq!(regions[0].holes[0].winding(), Some(Winding::Clockwise));
+        assert_area(&regions, 12.);
+        assert!(regions[0].contains(pt2(0.5, 0.5)));
+        assert!(regions[0].contains(pt2(1., 2.)));
+        assert!(!regions[0].contains(pt2(2., 2.)));
+    }
+
+    #[test]
+    fn offsets_with_mitred_corners() {
+        let square = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        assert_area(&square.offset(1., Join::Mitre), 16.);
+        assert_area(&square.offset(-0.5, Join::Mitre), 1.);
+        assert!(square.offset(-2., Join::Mitre).is_empty());
+
+        // Clockwise, to check that winding doesn't matter
+        let l_shape = polygon(&[(0., 0.), (0., 2.), (1., 2.), (1., 1.), (2., 1.), (2., 0.)]);
+        assert_area(&l_shape.offset(0.5, Join::Mitre), 8.);
+        assert_area(&l_shape.offset(-0.25, Join::Mitre), 1.25);
+    }
+
+    #[test]
+    fn offsets_with_round_corners() {
+        let square = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        let circle = Polygon::new(1., ROUND_JOIN_SIDES).area();
+        assert_area(&square.offset(1., Join::Round), 12. + circle);
+        let regions = square.offset(1., Join::Round);
+        assert!(regions[0].contains(pt2(-0.7, -0.7)));
+        assert!(!regions[0].contains(pt2(-0.8, -0.8)));
+    }
+
+    #[test]
+    fn very_sharp_mitres_are_cut_off() {
+        let spike = polygon(&[(0., 0.), (10., 0.5), (0., 1.)]);
+        let regions = spike.offset(1., Join::Mitre);
+        // Cut off across the ends of the two sides moved out, just past the tip
+        assert!(regions[0].contains(pt2(10.02, 0.5)));
+        assert!(!regions[0].contains(pt2(10.2, 0.5)));
+    }
+
+    #[test]
+    fn minkowski_sums() {
+        let square = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        let small = polygon(&[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]);
+        let sum = square.minkowski_sum(&small);
+        assert_eq!(sum.len(), 1);
+        assert_area(&sum, 9.);
+
+        let l_shape = polygon(&[(0., 0.), (2., 0.), (2., 1.), (1., 1.), (1., 2.), (0., 2.)]);
+        let nudge = polygon(&[(0., 0.), (0.5, 0.), (0.5, 0.5), (0., 0.5)]);
+        assert_area(&l_shape.minkowski_sum(&nudge), 5.25);
+        assert!(square.minkowski_sum(&Polygon { points: vec![] }).is_empty());
+    }
+
+    #[test]
+    fn stars_visit_every_point_once() {
+        let pentagram = Polygon::star(10., 5, 2).unwrap();
+        let pentagon = Polygon::new(10., 5);
+        assert_eq!(pentagram.points[1], pentagon.points[2]);
+        assert_eq!(pentagram.self_intersections().len(), 5);
+        assert_eq!(Polygon::star(10., 7, 1).unwrap(), Polygon::new(10., 7));
+        assert_eq!(Polygon::star(10., 7, 3).unwrap().validate(), Ok(()));
+
+        for (points, step) in [(6, 2), (5, 0), (5, 5), (2, 1)] {
+            assert_eq!(
+                Polygon::star(10., points, step),
+                Err(PolygonError::InvalidStar { points, step })
+            );
+        }
+    }
+
+    #[test]
+    fn rectangles_and_rhombi() {
+        let rectangle = Polygon::rectangle(4., 2.);
+        assert_eq!(rectangle.area(), 8.);
+        assert_eq!(rectangle.winding(), Some(Winding::Counterclockwise));
+        assert_close(rectangle.centroid().unwrap(), pt2(0., 0.));
+
+        let rhombus = Polygon::rhombus(2., PI / 6.);
+        assert!((rhombus.area() - 2.).abs() < 1e-5);
+        assert!((rhombus.perimeter() - 8.).abs() < 1e-5);
+        assert_eq!(rhombus.winding(), Some(Winding::Counterclockwise));
+        assert_close(rhombus.centroid().unwrap(), pt2(0., 0.));
+        let corner = rhombus.points[0];
+        let angle = angle_between_points(rhombus.points[1], corner, rhombus.points[3]);
+        assert!((angle - PI / 6.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn vertex_lists_are_validated() {
+        let points = vec![pt2(0., 0.), pt2(1., 0.), pt2(0., 1.)];
+        assert_eq!(
+            Polygon::from_vertices(points.clone()),
+            Ok(Polygon { points })
+        );
+        assert_eq!(
+            Polygon::from_vertices(vec![pt2(0., 0.), pt2(1., 0.)]),
+            Err(PolygonError::TooFewVertices(2))
+        );
+    }
+
+    #[test]
+    fn reads_svg_points() {
+        let triangle = Polygon::from_svg_points("0,0 10,0\n5 ,8.5").unwrap();
+        assert_eq!(
+            triangle.points,
+            vec![pt2(0., 0.), pt2(10., 0.), pt2(5., 8.5)]
+        );
+        let compact = Polygon::from_svg_points("-1-1 1e1-1.5.5 2").unwrap();
+        assert_eq!(
+            compact.points,
+            vec![pt2(-1., -1.), pt2(10., -1.5), pt2(0.5, 2.)]
+        );
+
+        assert!(matches!(
+            Polygon::from_svg_points("0,0 1,0 1"),
+            Err(PolygonError::InvalidSvg(_))
+        ));
+        assert!(matches!(
+            Polygon::from_svg_points("0,0 1,0 1,x"),
+            Err(PolygonError::InvalidSvg(_))
+        ));
+    }
+
+    #[test]
+    fn reads_svg_paths_of_straight_lines() {
+        let polygons = Polygon::from_svg_path("M0 0 L10 0 V10 H0 Z m20 0 l5 0 5 5 z").unwrap();
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(
+            polygons[0],
+            Polygon::from_svg_points("0,0 10,0 10,10 0,10").unwrap()
+        );
+        assert_eq!(
+            polygons[1].points,
+            vec![pt2(20., 0.), pt2(25., 0.), pt2(30., 5.)]
+        );
+
+        // Moves are followed by implicit lines, and going back to the start closes as well as Z
+        let square = Polygon::from_svg_path("M0,0 2,0 2,2 0,2 0,0").unwrap();
+        assert_eq!(square.len(), 1);
+        assert_eq!(square[0].points.len(), 4);
+
+        for data in [
+            "L0 0 1 0 1 1",
+            "M0 0 C1 1 2 2 3 3",
+            "M0 0 L1",
+            "M0 0 L1 1 Z",
+        ] {
+            assert!(Polygon::from_svg_path(data).is_err(), "{data} should fail");
+        }
+    }
+
+    fn coordinate() -> impl Strategy<Value = f32> {
+        -100f32..100.
+    }
+
+    fn any_point() -> impl Strategy<Value = Point2> {
+        (coordinate(), coordinate()).prop_map(|(x, y)| pt2(x, y))
+    }
+
+    fn any_line() -> impl Strategy<Value = Line> {
+        let extent = prop_oneof![Just(Extent::Line), Just(Extent::Ray), Just(Extent::Segment)];
+        (any_point(), any_point(), extent).prop_map(|(point1, point2, extent)| Line {
+            point1,
+            point2,
+            extent,
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn reflecting_twice_gives_the_point_back(line in any_line(), p in any_point()) {
+            let back = line.reflect_point(line.reflect_point(p));
+            prop_assert!(back.distance(p) < 0.01, "{} came back as {}", p, back);
+        }
+
+        #[test]
+        fn reflection_keeps_the_distance_to_the_line(line in any_line(), p in any_point()) {
+            prop_assume!(!line.is_degenerate());
+            let through = Line::through(line.point1, line.point2);
+            let reflected = line.reflect_point(p);
+            let difference = through.distance_to_point(p) - through.distance_to_point(reflected);
+            prop_assert!(difference.abs() < 0.01);
+        }
+
+        #[test]
+        fn intersections_are_on_both_lines(a in any_line(), b in any_line()) {
+            // Nearly parallel lines can meet far away, where f32 is coarser
+            let slack = |p: Point2| 0.01 + p.length() * 1e-4;
+            match a.intersection(&b) {
+                Intersection::Point(p) => {
+                    let slack = slack(p);
+                    prop_assert!(a.distance_to_point(p) < slack, "{} is off {:?}", p, a);
+                    prop_assert!(b.distance_to_point(p) < slack, "{} is off {:?}", p, b);
+                }
+                Intersection::Overlap(overlap) => {
+                    for t in [0., 0.5, 1.] {
+                        let p = overlap.point_at(t);
+                        let slack = slack(p);
+                        prop_assert!(a.distance_to_point(p) < slack);
+                        prop_assert!(b.distance_to_point(p) < slack);
+                    }
+                }
+                Intersection::None => (),
+            }
+        }
+
+        #[test]
+        fn intersection_is_symmetric(a in any_line(), b in any_line()) {
+            match (a.intersection(&b), b.intersection(&a)) {
+                (Intersection::Point(p), Intersection::Point(q)) => prop_assert!(p.distance(q) < 0.01 + p.length() * 1e-4),
+                (Intersection::None, Intersection::None) => (),
+                (Intersection::Overlap(_), Intersection::Overlap(_)) => (),
+                (one, other) => prop_assert!(false, "{:?} but {:?}", one, other),
+            }
+        }
+
+        #[test]
+        fn reflection_transform_matches_reflect_point(line in any_line(), p in any_point()) {
+            let reflected = Transform2D::reflect(&line).apply(p);
+            prop_assert!(reflected.distance(line.reflect_point(p)) < 0.01);
+        }
+
+        #[test]
+        fn inverse_undoes_a_transform(
+            translation in any_point(),
+            angle in -PI..PI,
+            scale in (0.1f32..10., 0.1f32..10.),
+            shear in (-0.9f32..0.9, -0.9f32..0.9),
+            p in any_point(),
+        ) {
+            let transform = Transform2D::translate(translation)
+                .then(Transform2D::rotate(angle))
+                .then(Transform2D::scale(vec2(scale.0, scale.1)));
+            let transform = transform.then(Transform2D::shear(shear.0, shear.1));
+            let inverse = transform.inverse().expect("transform should be invertible");
+            let back = inverse.apply(transform.apply(p));
+            prop_assert!(back.distance(p) < 0.01, "{} came back as {}", p, back);
+        }
+
+        #[test]
+        fn regular_polygons_are_convex_with_known_area(sides in 3usize..20, radius in 1f32..100.) {
+            let polygon = Polygon::new(radius, sides);
+            prop_assert!(polygon.is_convex());
+            let expected = sides as f32 * radius * radius * (2. * PI / sides as f32).sin() / 2.;
+            prop_assert!((polygon.area() - expected).abs() < expected * 1e-4);
+            prop_assert!(polygon.contains(polygon.centroid().unwrap()));
+        }
+
+        #[test]
+        fn transforms_scale_area_by_their_determinant(
+            sides in 3usize..10,
+            transform in (-PI..PI, 0.1f32..5., 0.1f32..5.),
+        ) {
+            let (angle, x, y) = transform;
+            let polygon = Polygon::new(10., sides);
+            let transform = Transform2D::rotate(angle).then(Transform2D::scale(vec2(x, y)));
+            let transformed = polygon.transformed(&transform);
+            let expected = polygon.signed_area() * x * y;
+            prop_assert!((transformed.signed_area() - expected).abs() < expected.abs() * 1e-3);
+        }
+
+        #[test]
+        fn boolean_operations_add_up(
+            a in (3usize..8, 1f32..50.),
+            b in (3usize..8, 1f32..50., any_point()),
+        ) {
+            let first = Polygon::new(a.1, a.0);
+            let mut second = Polygon::new(b.1, b.0);
+            second.translate(b.2 / 4.);
+            let union = total_area(&first.union(&second));
+            let intersection = total_area(&first.intersection(&second));
+            let slack = (first.area() + second.area()) * 1e-4;
+            prop_assert!((union + intersection - first.area() - second.area()).abs() < slack);
+            prop_assert!((total_area(&first.xor(&second)) - (union - intersection)).abs() < slack);
+            prop_assert!((total_area(&first.difference(&second)) - (first.area() - intersection)).abs() < slack);
+        }
+    }
+}
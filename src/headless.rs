@@ -0,0 +1,289 @@
+use crate::{
+    camera::Camera,
+    canvas::{Canvas, Geometry, Style},
+    record::{Format, Recording},
+    replay::{Replayer, Session},
+    settings_panel::Change,
+    sketch::{Context, Sketch},
+    timeline::{Timeline, TimelinePlayer},
+};
+use nannou::{
+    color::Srgba,
+    image::{ImageResult, RgbaImage},
+    prelude::*,
+};
+use std::path::PathBuf;
+use tiny_skia::{
+    FillRule, IntSize, Paint, Path, PathBuilder, Pixmap, PixmapPaint, Stroke, Transform,
+};
+
+/** Time that passes between frames when there's no display to set the pace */
+pub const FRAME_TIME: f32 = 1. / 60.;
+
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    pub frames: u32,
+    pub format: Format,
+    pub seed: u64,
+    /** Where to write the frames, without an extension */
+    pub out: PathBuf,
+    /** Recorded input to play back, after which the sketch is left to run by itself */
+    pub replay: Option<Session>,
+    /** Keyframes to play on the sketch's settings, when there's no input to replay */
+    pub timeline: Option<Timeline>,
+}
+
+/** Run a sketch without a window, recording every frame */
+pub fn render<S: Sketch>(options: &RenderOptions) -> ImageResult<()> {
+    let mut recording = Recording::new(options.format, options.format.path(options.out.clone()))?;
+    render_frames::<S>(options, |image| recording.push(image))?;
+    recording.finish()
+}
+
+/** Run a sketch without a window and return its last frame, ignoring format and out */
+pub fn snapshot<S: Sketch>(options: &RenderOptions) -> RgbaImage {
+    let mut last = None;
+    render_frames::<S>(options, |image| {
+        last = Some(image);
+        Ok(())
+    })
+    .expect("Keeping a frame can't fail");
+    last.unwrap_or_else(|| Rasteriser::new(options.width, options.height).image())
+}
+
+/** Run a sketch without a window, handing each frame over as it is rasterised */
+fn render_frames<S: Sketch>(
+    options: &RenderOptions,
+    mut frame: impl FnMut(RgbaImage) -> ImageResult<()>,
+) -> ImageResult<()> {
+    let window = Rect::from_w_h(options.width as f32, options.height as f32);
+    let mut replayer = options.replay.clone().map(Replayer::new);
+    let mut ctx = match &replayer {
+        Some(replayer) => replayer.context(),
+        None => Context::headless(window, options.seed),
+    };
+    let mut sketch = S::init(&ctx);
+    if let Some(error) = sketch.error() {
+        eprintln!("{error}");
+    }
+    // Replayed input already has its own pause toggles
+    if replayer.is_none() {
+        sketch.autoplay(&ctx);
+    }
+    let mut rasteriser = Rasteriser::new(options.width, options.height);
+    let mut player = options.timeline.clone().map(TimelinePlayer::new);
+
+    for _ in 0..options.frames {
+        // Once the input runs out, the sketch carries on in the last state it was replayed to
+        let replayed = replayer
+            .as_mut()
+            .is_some_and(|replayer| replayer.replay_frame(&mut sketch));
+        match &replayer {
+            Some(replayer) if replayed => ctx = replayer.context(),
+            _ => {
+                let keyframed = player
+                    .as_mut()
+                    .zip(sketch.settings())
+                    .and_then(|(player, settings)| player.advance(FRAME_TIME, &settings));
+                if let Some(values) = keyframed {
+                    if let Err(error) = sketch.apply_settings(&ctx, Change::Edited, values) {
+                        eprintln!("Failed to apply the timeline: {error}");
+                    }
+                }
+                sketch.update(&ctx, FRAME_TIME)
+            }
+        }
+        let mut canvas = Canvas::new();
+        sketch.draw(&ctx, &mut canvas);
+        rasteriser.draw(&canvas);
+        frame(rasteriser.image())?;
+    }
+    Ok(())
+}
+
+/**
+Rasterises canvases on the CPU.
+Like a window, the pixels are kept between frames unless the canvas sets a background.
+*/
+pub struct Rasteriser {
+    pixmap: Pixmap,
+    // Maps window coordinates, centred with y going up, to pixels with y going down
+    transform: Transform,
+}
+
+impl Rasteriser {
+    pub fn new(width: u32, height: u32) -> Self {
+        Rasteriser {
+            pixmap: Pixmap::new(width, height).expect("Image size should not be zero"),
+            transform: screen_transform(width, height),
+        }
+    }
+
+    /** Draw what the camera sees from now on, rather than the world as it is */
+    pub fn set_camera(&mut self, camera: &Camera) {
+        let Camera { centre, zoom } = *camera;
+        let (width, height) = (self.pixmap.width(), self.pixmap.height());
+        self.transform = screen_transform(width, height).pre_concat(Transform::from_row(
+            zoom,
+            0.,
+            0.,
+            zoom,
+            -centre.x * zoom,
+            -centre.y * zoom,
+        ));
+    }
+
+    pub fn draw(&mut self, canvas: &Canvas) {
+        if let Some(color) = canvas.background.and_then(to_skia_color) {
+            self.pixmap.fill(color);
+        }
+
+        for shape in &canvas.shapes {
+            let style = &shape.style;
+            match &shape.geometry {
+                Geometry::Polygon(points) => {
+                    if let Some(path) = polyline_path(points, true) {
+                        self.fill_and_outline(&path, style);
+                    }
+                }
+                Geometry::Rect(rect) => {
+                    let rect = tiny_skia::Rect::from_ltrb(
+                        rect.left(),
+                        rect.bottom(),
+                        rect.right(),
+                        rect.top(),
+                    );
+                    if let Some(rect) = rect {
+                        self.fill_and_outline(&PathBuilder::from_rect(rect), style);
+                    }
+                }
+                Geometry::Ellipse { centre, radius } => {
+                    if let Some(path) = PathBuilder::from_circle(centre.x, centre.y, *radius) {
+                        self.fill_and_outline(&path, style);
+                    }
+                }
+                Geometry::Polyline(points) => {
+                    if let (Some(path), Some(color)) = (polyline_path(points, false), style.color) {
+                        self.stroke(&path, color, style.stroke_weight);
+                    }
+                }
+                Geometry::Line { start, end } => {
+                    if let (Some(path), Some(color)) =
+                        (polyline_path(&[*start, *end], false), style.color)
+                    {
+                        self.stroke(&path, color, style.stroke_weight);
+                    }
+                }
+                Geometry::Image { image, rect } => {
+                    let image = image.to_rgba8();
+                    let Some(source) = to_pixmap(&image) else {
+                        continue;
+                    };
+                    // Image rows go down from the top of the rect
+                    let transform = self.transform.pre_concat(Transform::from_row(
+                        rect.w() / image.width() as f32,
+                        0.,
+                        0.,
+                        -rect.h() / image.height() as f32,
+                        rect.left(),
+                        rect.top(),
+                    ));
+                    self.pixmap.draw_pixmap(
+                        0,
+                        0,
+                        source.as_ref(),
+                        &PixmapPaint::default(),
+                        transform,
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn image(&self) -> RgbaImage {
+        let data = self
+            .pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let pixel = pixel.demultiply();
+                [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+            })
+            .collect();
+        RgbaImage::from_raw(self.pixmap.width(), self.pixmap.height(), data)
+            .expect("Pixmap should have four bytes per pixel")
+    }
+
+    fn fill_and_outline(&mut self, path: &Path, style: &Style) {
+        if let Some(paint) = style.color.and_then(paint) {
+            self.pixmap
+                .fill_path(path, &paint, FillRule::EvenOdd, self.transform, None);
+        }
+        if let Some(color) = style.stroke {
+            self.stroke(path, color, style.stroke_weight);
+        }
+    }
+
+    fn stroke(&mut self, path: &Path, color: Srgba, weight: f32) {
+        let Some(paint) = paint(color) else {
+            return;
+        };
+        let stroke = Stroke {
+            width: weight,
+            ..Default::default()
+        };
+        self.pixmap
+            .stroke_path(path, &paint, &stroke, self.transform, None);
+    }
+}
+
+fn screen_transform(width: u32, height: u32) -> Transform {
+    Transform::from_row(1., 0., 0., -1., width as f32 / 2., height as f32 / 2.)
+}
+
+fn polyline_path(points: &[Point2], close: bool) -> Option<Path> {
+    let (first, rest) = points.split_first()?;
+    let mut builder = PathBuilder::new();
+    builder.move_to(first.x, first.y);
+    for point in rest {
+        builder.line_to(point.x, point.y);
+    }
+    if close {
+        builder.close();
+    }
+    builder.finish()
+}
+
+fn to_pixmap(image: &RgbaImage) -> Option<Pixmap> {
+    let data = image
+        .pixels()
+        .flat_map(|pixel| {
+            let [red, green, blue, alpha] = pixel.0;
+            let color = tiny_skia::ColorU8::from_rgba(red, green, blue, alpha).premultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Pixmap::from_vec(data, IntSize::from_wh(image.width(), image.height())?)
+}
+
+fn paint(color: Srgba) -> Option<Paint<'static>> {
+    let mut paint = Paint::default();
+    paint.set_color(to_skia_color(color)?);
+    paint.anti_alias = true;
+    Some(paint)
+}
+
+/**
+Channels are clamped, as colours like three_points' hsv can go out of range.
+Returns None for NaN channels, which are skipped rather than drawn.
+*/
+fn to_skia_color(color: Srgba) -> Option<tiny_skia::Color> {
+    tiny_skia::Color::from_rgba(
+        color.red.clamp(0., 1.),
+        color.green.clamp(0., 1.),
+        color.blue.clamp(0., 1.),
+        color.alpha.clamp(0., 1.),
+    )
+}
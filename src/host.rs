@@ -0,0 +1,785 @@
+use crate::{
+    bindings::key_name,
+    camera::Camera,
+    canvas::{Canvas, Geometry, Style},
+    clock::{ClockAction, SimClock, MAX_SPEED, MIN_SPEED},
+    control::{self, ControlServer, Reply, Request},
+    headless::{Rasteriser, FRAME_TIME},
+    perf::{Perf, PerfLog},
+    record::{RecordOptions, Recording},
+    replay::{Event, Replayer, Session, SessionRecorder},
+    settings_panel::Change,
+    sketch::{Context, Input, Sketch},
+    svg,
+    timeline::{Timeline, TimelineEditor},
+};
+use nannou::{
+    image::DynamicImage,
+    prelude::*,
+    wgpu::{self, Texture},
+};
+use nannou_egui::{egui, Egui};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    fs,
+    path::PathBuf,
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const ELLIPSE_RESOLUTION: usize = 64;
+const PAUSE_KEY: Key = Key::F5;
+const STEP_KEY: Key = Key::F6;
+const SLOWER_KEY: Key = Key::F7;
+const FASTER_KEY: Key = Key::F8;
+const PERF_KEY: Key = Key::F3;
+const TIMELINE_KEY: Key = Key::F4;
+const RECORD_KEY: Key = Key::F9;
+const SVG_KEY: Key = Key::F10;
+const RESET_CAMERA_KEY: Key = Key::Home;
+/** Button that pans the camera when dragged, which isn't passed on to sketches */
+const PAN_BUTTON: MouseButton = MouseButton::Middle;
+/** How much one line of scrolling zooms by */
+const ZOOM_STEP: f32 = 1.1;
+/** Scrolling in pixels, like on a touchpad, zooms by a step every this many pixels */
+const PIXELS_PER_ZOOM_STEP: f32 = 50.;
+/** Shown as ? in the help, as that's the same key on most layouts */
+const HELP_KEY: Key = Key::Slash;
+/** Keys the host handles before the sketch sees them, so that sketches can't be bound to them */
+pub const HOST_KEYS: &[Key] = &[
+    PERF_KEY,
+    TIMELINE_KEY,
+    PAUSE_KEY,
+    STEP_KEY,
+    SLOWER_KEY,
+    FASTER_KEY,
+    RECORD_KEY,
+    SVG_KEY,
+    RESET_CAMERA_KEY,
+    HELP_KEY,
+];
+
+/** Options from the command line, which nannou's model function has no way to be given */
+static RUN_OPTIONS: OnceLock<RunOptions> = OnceLock::new();
+
+#[derive(Clone, Debug)]
+pub struct RunOptions {
+    /** Shown in the title of the window */
+    pub name: &'static str,
+    pub seed: u64,
+    pub record: RecordOptions,
+    /** Where to save the input of the run when the window is closed, so that it can be replayed */
+    pub record_input: Option<PathBuf>,
+    /** A recorded run to play back before handing the sketch over */
+    pub replay: Option<Session>,
+    /** Port on localhost to serve the sketch's settings on, see the control module */
+    pub control: Option<u16>,
+    /** Show the performance overlay from the start */
+    pub perf: bool,
+    /** Where to log the time every frame took as CSV */
+    pub perf_log: Option<PathBuf>,
+    /** Keyframes to play on the sketch's settings from the start */
+    pub timeline: Option<Timeline>,
+}
+
+/** Wraps a sketch with the window and egui state that every sketch shares */
+struct Host<S: Sketch> {
+    sketch: S,
+    egui: Egui,
+    recording: Option<(Recording, Rasteriser)>,
+    show_help: bool,
+    session: Option<SessionRecorder>,
+    /** Input from the window is ignored until the replay has finished */
+    replayer: Option<Replayer>,
+    control: Option<ControlServer>,
+    camera: Camera,
+    /** Where the mouse was on screen while the camera is being dragged */
+    drag: Option<Point2>,
+    perf: Perf,
+    show_perf: bool,
+    timeline: TimelineEditor,
+    show_timeline: bool,
+    textures: ImageTextures,
+}
+
+/** Open a window and run the sketch until the window is closed */
+pub fn run<S: Sketch>(options: RunOptions) {
+    RUN_OPTIONS
+        .set(options)
+        .expect("Only one sketch should be run per process");
+    nannou::app(model::<S>)
+        .update(update::<S>)
+        .exit(exit::<S>)
+        .run();
+}
+
+fn run_options() -> &'static RunOptions {
+    RUN_OPTIONS.get().expect("Run options should be set by run")
+}
+
+/** What the sketch is told about the world, with the mouse where it is in the world rather than on screen */
+fn context<S: Sketch>(app: &App, host: &Host<S>) -> Context {
+    let ctx = Context::from_app(app, run_options().seed);
+    ctx.with_mouse(host.camera.to_world(ctx.mouse()))
+}
+
+fn model<S: Sketch>(app: &App) -> Host<S> {
+    let mut options = S::window();
+    let run_options = run_options();
+    let mut title = format!("{} (seed {})", run_options.name, run_options.seed);
+    if let Some(session) = &run_options.replay {
+        // The sketch should see the same window that it was recorded in
+        let [width, height] = session.window;
+        options.size = Some([width as u32, height as u32]);
+        options.fullscreen = false;
+        title.push_str(" replaying");
+    }
+    let mut builder = app
+        .new_window()
+        .title(title)
+        .view(view::<S>)
+        .raw_event(raw_window_event::<S>)
+        .key_pressed(key_pressed::<S>)
+        .key_released(key_released::<S>)
+        .mouse_pressed(mouse_pressed::<S>)
+        .mouse_released(mouse_released::<S>)
+        .mouse_moved(mouse_moved::<S>)
+        .mouse_wheel(mouse_wheel::<S>);
+    if let Some([width, height]) = options.size {
+        builder = builder.size(width, height);
+    }
+    if options.fullscreen {
+        builder = builder.fullscreen();
+    }
+    let window_id = builder.build().unwrap();
+
+    let ctx = Context::from_app(app, run_options.seed);
+    let replayer = run_options.replay.clone().map(Replayer::new);
+    let sketch = match &replayer {
+        Some(replayer) => S::init(&replayer.session().init_context()),
+        None => S::init(&ctx),
+    };
+    let session = run_options.record_input.as_ref().map(|_| {
+        SessionRecorder::new(Session::new(
+            run_options.name,
+            run_options.seed,
+            ctx.window_rect(),
+        ))
+    });
+
+    let control =
+        run_options
+            .control
+            .and_then(|port| match ControlServer::start(run_options.name, port) {
+                Ok(server) => {
+                    println!("Serving settings on ws://localhost:{}", server.port());
+                    Some(server)
+                }
+                Err(error) => {
+                    eprintln!("Failed to serve settings on port {port}: {error}");
+                    None
+                }
+            });
+
+    let perf_log = run_options.perf_log.as_ref().and_then(|path| {
+        PerfLog::create(path)
+            .inspect_err(|error| {
+                eprintln!("Failed to log performance to {}: {error}", path.display())
+            })
+            .ok()
+    });
+
+    Host {
+        sketch,
+        egui: Egui::from_window(&app.window(window_id).unwrap()),
+        recording: None,
+        show_help: false,
+        session,
+        replayer,
+        control,
+        camera: Camera::default(),
+        drag: None,
+        perf: Perf::new(perf_log),
+        show_perf: run_options.perf,
+        timeline: TimelineEditor::new(
+            &ctx,
+            run_options.name,
+            run_options.timeline.clone().unwrap_or_default(),
+        ),
+        show_timeline: false,
+        textures: ImageTextures::default(),
+    }
+}
+
+fn raw_window_event<S: Sketch>(
+    _app: &App,
+    host: &mut Host<S>,
+    event: &nannou::winit::event::WindowEvent,
+) {
+    // Let egui handle things like keyboard and mouse input.
+    host.egui.handle_raw_event(event);
+}
+
+fn update<S: Sketch>(app: &App, host: &mut Host<S>, update: Update) {
+    let ctx = context(app, host);
+    host.egui.set_elapsed_time(update.since_start);
+    let mut clock_action = None;
+    {
+        let egui_ctx = host.egui.begin_frame();
+        if S::HAS_SETTINGS {
+            let window = app.window_rect();
+            egui::Window::new("Settings")
+                .default_pos([window.x.len() / 10., window.y.len() / 4.])
+                .show(&egui_ctx, |ui| {
+                    if host.replayer.is_some() {
+                        ui.label("Settings can be changed once the replay has finished");
+                    } else if let Some(change) = host.sketch.settings_ui(&ctx, ui) {
+                        // The sketch has already applied the change, it only needs recording
+                        if let (Some(session), Some(values)) =
+                            (&mut host.session, host.sketch.settings())
+                        {
+                            session.event(&ctx, Event::Settings { change, values });
+                        }
+                    }
+                    ui.separator();
+                    ui.label(format!("Seed: {}", ctx.seed()));
+                });
+        }
+        if host.show_help {
+            egui::Window::new("Help").show(&egui_ctx, |ui| {
+                egui::Grid::new("keys").striped(true).show(ui, |ui| {
+                    let mut host_keys = vec![];
+                    if host.sketch.clock().is_some() {
+                        host_keys.extend([
+                            (key_name(PAUSE_KEY), "Pause or resume the simulation"),
+                            (key_name(STEP_KEY), "Advance the simulation one step"),
+                            (key_name(SLOWER_KEY), "Halve the simulation speed"),
+                            (key_name(FASTER_KEY), "Double the simulation speed"),
+                        ]);
+                    }
+                    host_keys.extend([
+                        ("Middle drag".to_string(), "Pan the view"),
+                        ("Scroll".to_string(), "Zoom the view in or out"),
+                        (key_name(RESET_CAMERA_KEY), "Reset the pan and zoom"),
+                        (key_name(PERF_KEY), "Show or hide the performance overlay"),
+                        (key_name(TIMELINE_KEY), "Show or hide the timeline"),
+                        (key_name(RECORD_KEY), "Start or stop recording"),
+                        (key_name(SVG_KEY), "Save the frame as an SVG"),
+                        ("?".to_string(), "Show or hide this help"),
+                    ]);
+                    for (key, description) in host_keys.into_iter().chain(host.sketch.help()) {
+                        ui.label(key);
+                        ui.label(description);
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        if let Some(clock) = host.sketch.clock().filter(|_| host.replayer.is_none()) {
+            let action = egui::Window::new("Clock")
+                .anchor(egui::Align2::RIGHT_TOP, [-10., 10.])
+                .resizable(false)
+                .show(&egui_ctx, |ui| clock_ui(ui, clock))
+                .and_then(|response| response.inner.flatten());
+            clock_action = action;
+        }
+        if let Some(settings) = host.sketch.settings().filter(|_| host.show_timeline) {
+            let window = app.window_rect();
+            egui::Window::new("Timeline")
+                .default_pos([window.x.len() / 2., window.y.len() / 4.])
+                .show(&egui_ctx, |ui| host.timeline.ui(ui, &settings));
+        }
+        if host.show_perf {
+            egui::Area::new("perf")
+                .anchor(egui::Align2::LEFT_TOP, [10., 10.])
+                .show(&egui_ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        egui::Grid::new("perf_lines").show(ui, |ui| {
+                            for (name, value) in host.perf.summary(&host.sketch.counters()) {
+                                ui.label(name);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                    });
+                });
+        }
+        if let Some(error) = host.sketch.error() {
+            egui::Area::new("error")
+                .anchor(egui::Align2::LEFT_BOTTOM, [10., -10.])
+                .show(&egui_ctx, |ui| {
+                    egui::Frame::popup(ui.style())
+                        .show(ui, |ui| ui.colored_label(egui::Color32::RED, error));
+                });
+        }
+    }
+
+    if let Some(action) = clock_action {
+        apply_event(&ctx, host, Event::Clock(action));
+    }
+    handle_control_requests(&ctx, host);
+
+    let update_start = Instant::now();
+    if let Some(replayer) = &mut host.replayer {
+        if replayer.replay_frame(&mut host.sketch) {
+            let ctx = replayer.context();
+            record_perf(host, update.since_last, update_start);
+            publish_settings(host);
+            record_frame(&ctx, host);
+            return;
+        }
+        println!("Finished replaying, the sketch takes input from the window again");
+        host.replayer = None;
+    }
+
+    // Recordings use a fixed timestep so that they play back at the right speed
+    let dt = if host.recording.is_some() {
+        FRAME_TIME
+    } else {
+        update.since_last.as_secs_f32()
+    };
+    let keyframed = host
+        .sketch
+        .settings()
+        .and_then(|settings| host.timeline.player.advance(dt, &settings));
+    if let Some(values) = keyframed {
+        let change = Change::Edited;
+        apply_event(&ctx, host, Event::Settings { change, values });
+    }
+    host.sketch.update(&ctx, dt);
+    record_perf(host, update.since_last, update_start);
+    if let Some(session) = &mut host.session {
+        session.frame(&ctx, dt);
+    }
+    publish_settings(host);
+    record_frame(&ctx, host);
+}
+
+fn record_perf<S: Sketch>(host: &mut Host<S>, frame: Duration, update_start: Instant) {
+    let counters = host.sketch.counters();
+    host.perf
+        .record_frame(frame, update_start.elapsed(), &counters);
+}
+
+/** Save the input of the run, if it was asked for */
+fn exit<S: Sketch>(_app: &App, host: Host<S>) {
+    let (Some(session), Some(path)) = (host.session, &run_options().record_input) else {
+        return;
+    };
+    match session.session().save(path) {
+        Ok(()) => println!("Saved input to {}", path.display()),
+        Err(error) => eprintln!("Failed to save input to {}: {error}", path.display()),
+    }
+}
+
+/** Record input from the window, then pass it on to the sketch */
+fn apply_event<S: Sketch>(ctx: &Context, host: &mut Host<S>, event: Event) {
+    if host.replayer.is_some() {
+        return;
+    }
+    if let Some(session) = &mut host.session {
+        session.event(ctx, event.clone());
+    }
+    if let Err(error) = event.apply(&mut host.sketch, ctx) {
+        eprintln!("{error}");
+    }
+}
+
+/** Apply settings sent to the control server, as if they were changed in the settings window */
+fn handle_control_requests<S: Sketch>(ctx: &Context, host: &mut Host<S>) {
+    let Some(requests) = host.control.as_ref().map(ControlServer::requests) else {
+        return;
+    };
+    for client in requests {
+        let reply = match client.request {
+            Request::Get => Reply::answer(run_options().name, host.sketch.settings()),
+            // Changes are sent to every client once the frame is done
+            Request::Set {
+                ref parameter,
+                ref value,
+            } => match set_parameter(ctx, host, parameter, value.clone()) {
+                Ok(()) => continue,
+                Err(message) => Reply::Error { message },
+            },
+        };
+        client.reply(&reply);
+    }
+}
+
+fn set_parameter<S: Sketch>(
+    ctx: &Context,
+    host: &mut Host<S>,
+    parameter: &str,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    if host.replayer.is_some() {
+        return Err("settings can be changed once the replay has finished".to_string());
+    }
+    let settings = host
+        .sketch
+        .settings()
+        .ok_or_else(|| format!("{} has no settings", run_options().name))?;
+    let values = control::set_parameter(&settings, parameter, value)
+        .ok_or_else(|| format!("no parameter called {parameter}"))?;
+    let change = Change::Edited;
+    host.sketch
+        .apply_settings(ctx, change, values.clone())
+        .map_err(|error| format!("can't set {parameter}: {error}"))?;
+    if let Some(session) = &mut host.session {
+        session.event(ctx, Event::Settings { change, values });
+    }
+    Ok(())
+}
+
+/** Let control clients know about changes to the settings, however they were made */
+fn publish_settings<S: Sketch>(host: &mut Host<S>) {
+    if let Some(control) = &mut host.control {
+        control.publish(host.sketch.settings());
+    }
+}
+
+fn clock_ui(ui: &mut egui::Ui, clock: &SimClock) -> Option<ClockAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui
+            .button(if clock.paused() { "Resume" } else { "Pause" })
+            .clicked()
+        {
+            action = Some(ClockAction::TogglePaused);
+        }
+        if ui.button("Step").clicked() {
+            action = Some(ClockAction::Step);
+        }
+    });
+    let mut speed = clock.speed();
+    let slider = egui::Slider::new(&mut speed, MIN_SPEED..=MAX_SPEED)
+        .logarithmic(true)
+        .text("Speed");
+    if ui.add(slider).changed() {
+        action = Some(ClockAction::SetSpeed(speed));
+    }
+    action
+}
+
+/** Start recording, or stop and save the recording if one is in progress */
+fn toggle_recording<S: Sketch>(ctx: &Context, host: &mut Host<S>) {
+    if host.recording.is_some() {
+        finish_recording(host);
+        return;
+    }
+
+    let options = &run_options().record;
+    let path = options.format.path(options.out.join(timestamp()));
+    match Recording::new(options.format, path) {
+        Ok(recording) => {
+            println!(
+                "Recording {} frames to {}, press {RECORD_KEY:?} to stop early",
+                options.frames,
+                recording.path().display()
+            );
+            let window = ctx.window_rect();
+            let rasteriser = Rasteriser::new(window.w() as u32, window.h() as u32);
+            host.recording = Some((recording, rasteriser));
+        }
+        Err(error) => eprintln!("Failed to start recording: {error}"),
+    }
+}
+
+/** Save the shapes of the current frame as an SVG next to the recordings, as far as the camera sees */
+fn save_svg<S: Sketch>(ctx: &Context, host: &Host<S>) {
+    let mut canvas = Canvas::new();
+    host.sketch.draw(ctx, &mut canvas);
+    let out = &run_options().record.out;
+    let path = out.join(timestamp()).with_extension("svg");
+    let saved = fs::create_dir_all(out).and_then(|_| {
+        fs::write(
+            &path,
+            svg::to_svg(&canvas, host.camera.visible_rect(ctx.window_rect())),
+        )
+    });
+    match saved {
+        Ok(()) => println!("Saved SVG to {}", path.display()),
+        Err(error) => eprintln!("Failed to save SVG: {error}"),
+    }
+}
+
+fn timestamp() -> String {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn record_frame<S: Sketch>(ctx: &Context, host: &mut Host<S>) {
+    let Some((recording, rasteriser)) = &mut host.recording else {
+        return;
+    };
+    let mut canvas = Canvas::new();
+    host.sketch.draw(ctx, &mut canvas);
+    rasteriser.set_camera(&host.camera);
+    rasteriser.draw(&canvas);
+    if let Err(error) = recording.push(rasteriser.image()) {
+        eprintln!("Failed to record frame: {error}");
+        host.recording = None;
+        return;
+    }
+
+    if recording.frames_pushed() >= run_options().record.frames {
+        finish_recording(host);
+    }
+}
+
+/** The writer can still be catching up, so it's waited for off the main thread to keep the window responsive */
+fn finish_recording<S: Sketch>(host: &mut Host<S>) {
+    let Some((recording, _)) = host.recording.take() else {
+        return;
+    };
+    thread::spawn(move || {
+        let path = recording.path().clone();
+        match recording.finish() {
+            Ok(()) => println!("Saved recording to {}", path.display()),
+            Err(error) => eprintln!("Failed to save recording: {error}"),
+        }
+    });
+}
+
+fn view<S: Sketch>(app: &App, host: &Host<S>, frame: Frame) {
+    let view_start = Instant::now();
+    let mut canvas = Canvas::new();
+    host.sketch.draw(&context(app, host), &mut canvas);
+    let draw = app.draw();
+    let camera = &host.camera;
+    let world = draw.scale(camera.zoom).translate(-camera.centre.extend(0.));
+    draw_canvas(app, &canvas, &world, &host.textures);
+
+    // put everything on the frame
+    draw.to_frame(app, &frame).unwrap();
+    // Tessellating the shapes in to_frame is most of the work for sketches with many of them
+    host.perf
+        .record_view(view_start.elapsed(), canvas.shapes.len());
+    host.egui.draw_to_frame(&frame).unwrap();
+}
+
+fn key_pressed<S: Sketch>(app: &App, host: &mut Host<S>, key: Key) {
+    if host.egui.ctx().wants_keyboard_input() {
+        return;
+    }
+    let ctx = context(app, host);
+    if key == RECORD_KEY {
+        toggle_recording(&ctx, host);
+        return;
+    }
+    if key == SVG_KEY {
+        save_svg(&ctx, host);
+        return;
+    }
+    if key == HELP_KEY {
+        host.show_help = !host.show_help;
+        return;
+    }
+    if key == TIMELINE_KEY {
+        host.show_timeline = !host.show_timeline;
+        return;
+    }
+    if key == PERF_KEY {
+        host.show_perf = !host.show_perf;
+        return;
+    }
+    if key == RESET_CAMERA_KEY {
+        host.camera.reset();
+        return;
+    }
+    if let Some(clock) = host.sketch.clock() {
+        let action = match key {
+            PAUSE_KEY => Some(ClockAction::TogglePaused),
+            STEP_KEY => Some(ClockAction::Step),
+            SLOWER_KEY => Some(ClockAction::SetSpeed(clock.speed() / 2.)),
+            FASTER_KEY => Some(ClockAction::SetSpeed(clock.speed() * 2.)),
+            _ => None,
+        };
+        if let Some(action) = action {
+            apply_event(&ctx, host, Event::Clock(action));
+            return;
+        }
+    }
+    apply_event(&ctx, host, Event::Input(Input::KeyPressed(key)));
+}
+
+fn key_released<S: Sketch>(app: &App, host: &mut Host<S>, key: Key) {
+    if host.egui.ctx().wants_keyboard_input() {
+        return;
+    }
+    let ctx = context(app, host);
+    apply_event(&ctx, host, Event::Input(Input::KeyReleased(key)));
+}
+
+fn mouse_pressed<S: Sketch>(app: &App, host: &mut Host<S>, button: MouseButton) {
+    if host.egui.ctx().wants_pointer_input() {
+        return;
+    }
+    if button == PAN_BUTTON {
+        host.drag = Some(app.mouse.position());
+        return;
+    }
+    let ctx = context(app, host);
+    let input = Input::MousePressed {
+        button,
+        position: ctx.mouse(),
+    };
+    apply_event(&ctx, host, Event::Input(input));
+}
+
+fn mouse_released<S: Sketch>(_app: &App, host: &mut Host<S>, button: MouseButton) {
+    if button == PAN_BUTTON {
+        host.drag = None;
+    }
+}
+
+fn mouse_moved<S: Sketch>(_app: &App, host: &mut Host<S>, position: Point2) {
+    if let Some(from) = &mut host.drag {
+        host.camera.pan(position - *from);
+        *from = position;
+    }
+}
+
+fn mouse_wheel<S: Sketch>(
+    app: &App,
+    host: &mut Host<S>,
+    delta: MouseScrollDelta,
+    _phase: TouchPhase,
+) {
+    if host.egui.ctx().wants_pointer_input() {
+        return;
+    }
+    let steps = match delta {
+        MouseScrollDelta::LineDelta(_, lines) => lines,
+        MouseScrollDelta::PixelDelta(pixels) => pixels.y as f32 / PIXELS_PER_ZOOM_STEP,
+    };
+    host.camera
+        .zoom_at(app.mouse.position(), ZOOM_STEP.powf(steps));
+}
+
+/**
+Textures for the images on a canvas, in the order they are drawn, kept from one frame to the next.
+Sketches that rasterise themselves draw an image the same size every frame,
+so its new pixels are uploaded into last frame's texture rather than making a new one each time.
+*/
+#[derive(Default)]
+pub struct ImageTextures {
+    /** Drawing only borrows whatever is being drawn, so the textures are swapped in place */
+    textures: RefCell<Vec<Texture>>,
+}
+
+impl ImageTextures {
+    fn texture(&self, app: &App, index: usize, image: &DynamicImage) -> Texture {
+        let image = match image {
+            DynamicImage::ImageRgba8(buffer) => Cow::Borrowed(buffer),
+            image => Cow::Owned(image.to_rgba8()),
+        };
+        let window = app.main_window();
+        let mut textures = self.textures.borrow_mut();
+        if let Some(texture) = textures.get(index) {
+            if texture.size() == [image.width(), image.height()] {
+                let layout = wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(image.width() * 4),
+                    rows_per_image: None,
+                };
+                window.queue().write_texture(
+                    texture.as_image_copy(),
+                    image.as_raw(),
+                    layout,
+                    texture.extent(),
+                );
+                return texture.clone();
+            }
+        }
+
+        let texture = Texture::load_from_image_buffer(
+            window.device(),
+            window.queue(),
+            wgpu::TextureBuilder::default_image_texture_usage(),
+            &*image,
+        );
+        textures.truncate(index);
+        textures.push(texture.clone());
+        texture
+    }
+
+    /** Drop the textures of images that are no longer drawn */
+    fn keep(&self, count: usize) {
+        self.textures.borrow_mut().truncate(count);
+    }
+}
+
+/** Replay the shapes of a canvas onto a nannou Draw */
+pub fn draw_canvas(app: &App, canvas: &Canvas, draw: &Draw, textures: &ImageTextures) {
+    if let Some(color) = canvas.background {
+        draw.background().color(color);
+    }
+
+    let mut images = 0;
+    for shape in &canvas.shapes {
+        let style = &shape.style;
+        match &shape.geometry {
+            Geometry::Polygon(points) => {
+                if let Some(color) = style.color {
+                    draw.polygon().points(points.iter().copied()).color(color);
+                }
+                draw_outline(draw, style, points.iter().copied());
+            }
+            Geometry::Rect(rect) => {
+                if let Some(color) = style.color {
+                    draw.rect().xy(rect.xy()).wh(rect.wh()).color(color);
+                }
+                draw_outline(draw, style, rect.corners_iter().map(Point2::from));
+            }
+            Geometry::Ellipse { centre, radius } => {
+                if let Some(color) = style.color {
+                    draw.ellipse().xy(*centre).radius(*radius).color(color);
+                }
+                let outline = (0..ELLIPSE_RESOLUTION).map(|i| {
+                    let angle = i as f32 / ELLIPSE_RESOLUTION as f32 * 2. * PI;
+                    *centre + vec2(angle.cos(), angle.sin()) * *radius
+                });
+                draw_outline(draw, style, outline);
+            }
+            Geometry::Polyline(points) => {
+                if let Some(color) = style.color {
+                    draw.polyline()
+                        .weight(style.stroke_weight)
+                        .points(points.iter().copied())
+                        .color(color);
+                }
+            }
+            Geometry::Line { start, end } => {
+                if let Some(color) = style.color {
+                    draw.line()
+                        .start(*start)
+                        .end(*end)
+                        .weight(style.stroke_weight)
+                        .color(color);
+                }
+            }
+            Geometry::Image { image, rect } => {
+                draw.texture(&textures.texture(app, images, image))
+                    .xy(rect.xy())
+                    .wh(rect.wh());
+                images += 1;
+            }
+        }
+    }
+    textures.keep(images);
+}
+
+fn draw_outline<I: IntoIterator<Item = Point2>>(draw: &Draw, style: &Style, points: I) {
+    if let Some(color) = style.stroke {
+        draw.polyline()
+            .weight(style.stroke_weight)
+            .points_closed(points)
+            .color(color);
+    }
+}
@@ -0,0 +1,53 @@
+/*!
+Sketches made with nannou, and the pieces they are built from.
+The simulations don't need a window, so they can be used from other tools and tests.
+*/
+
+pub mod bindings;
+pub mod camera;
+pub mod canvas;
+pub mod clock;
+pub mod config;
+pub mod control;
+pub mod gallery;
+pub mod geometry;
+pub mod headless;
+pub mod host;
+pub mod perf;
+pub mod presets;
+pub mod record;
+pub mod registry;
+pub mod replay;
+pub mod settings_panel;
+pub mod sketch;
+pub mod svg;
+pub mod timeline;
+/** Window-free cores of the projects, with plain-data state and step functions */
+pub mod simulations {
+    pub mod boids;
+    pub mod contour_lines;
+    pub mod distance_between_points;
+    pub mod epicyclogons;
+    pub mod four_tempers;
+    pub mod game_of_life;
+    pub mod recursive_rotation;
+    pub mod three_points;
+}
+/** Sketches that show the simulations and other experiments in a window */
+pub mod projects {
+    pub mod boids;
+    pub mod digits_to_zero;
+    pub mod distance_between_points;
+    pub mod epicyclogons;
+    pub mod game_of_life;
+    pub mod three_points;
+    pub mod severance {
+        pub mod colors;
+        pub mod contour_lines;
+        pub mod four_tempers;
+        pub mod recursive_rotation;
+    }
+    pub mod tesselations {
+        pub mod tesselation_editor;
+    }
+}
@@ -1,15 +1,365 @@
-use projects::severance::four_tempers::{update, Model};
-
-pub mod geometry;
-pub mod projects {
-    pub mod severance {
-        pub mod colors;
-        // pub mod contour_lines;
-        pub mod four_tempers;
-        pub mod recursive_rotation;
-    }
+use clap::{builder::PossibleValuesParser, Parser, Subcommand};
+use nannou::rand::random;
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+use workshop::{
+    control,
+    gallery::{self, GalleryOptions},
+    headless::RenderOptions,
+    host::RunOptions,
+    record::{Format, RecordOptions},
+    registry,
+    replay::Session,
+    svg::SvgOptions,
+    timeline::Timeline,
+};
+
+#[derive(Parser)]
+#[command(version, about = "A collection of nannou sketches")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every sketch that can be run
+    List,
+    /// Open a sketch in a new window, press ? in it for its keys, F9 to start or stop recording and F10 to save an SVG
+    Run {
+        #[arg(value_parser = sketch_names())]
+        sketch: String,
+        /// Number of frames to record after pressing F9
+        #[arg(long, default_value_t = 120)]
+        record_frames: u32,
+        #[arg(long, value_enum, default_value_t = Format::Gif)]
+        record_format: Format,
+        /// Folder to save recordings in [default: recordings/<SKETCH>]
+        #[arg(long)]
+        record_out: Option<PathBuf>,
+        /// Save every key, click and change of settings to this TOML or JSON file, to replay later
+        #[arg(long, value_name = "PATH")]
+        record_input: Option<PathBuf>,
+        /// Serve the sketch's settings over WebSocket on this port of localhost, see the control command
+        #[arg(long, value_name = "PORT")]
+        control: Option<u16>,
+        /// Show the performance overlay from the start, F3 shows or hides it
+        #[arg(long)]
+        perf: bool,
+        /// Log the time every frame took, and the sketch's counters, to this CSV file
+        #[arg(long, value_name = "PATH")]
+        perf_log: Option<PathBuf>,
+        /// Play the keyframes in this TOML or JSON file on the sketch's settings, F4 shows them
+        #[arg(long, value_name = "PATH")]
+        timeline: Option<PathBuf>,
+        /// Seed for everything the sketch randomises [default: random]
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Play back input saved with run --record-input, in a window or headless
+    Replay {
+        file: PathBuf,
+        /// Render frames on the CPU instead of opening a window
+        #[arg(long)]
+        headless: bool,
+        /// Log the time every frame took to this CSV file, to compare runs of the same input
+        #[arg(long, value_name = "PATH", conflicts_with = "headless")]
+        perf_log: Option<PathBuf>,
+        /// Frames to render when headless, which can go past the end of the input [default: as many as were recorded]
+        #[arg(long)]
+        frames: Option<u32>,
+        #[arg(long, value_enum, default_value_t = Format::Png)]
+        format: Format,
+        /// Where to write the frames when headless, without an extension [default: renders/<SKETCH>-replay]
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Render frames of a sketch to PNGs on the CPU, without a window or GPU
+    Render {
+        #[arg(value_parser = sketch_names())]
+        sketch: String,
+        #[arg(long, default_value_t = 800)]
+        width: u32,
+        #[arg(long, default_value_t = 600)]
+        height: u32,
+        #[arg(long, default_value_t = 1)]
+        frames: u32,
+        #[arg(long, value_enum, default_value_t = Format::Png)]
+        format: Format,
+        /// Where to write the frames, without an extension [default: renders/<SKETCH>]
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Play the keyframes in this TOML or JSON file on the sketch's settings
+        #[arg(long, value_name = "PATH")]
+        timeline: Option<PathBuf>,
+        /// Seed for everything the sketch randomises [default: random]
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Show sketches one after another full screen, until a key is pressed
+    Gallery {
+        /// Sketches to show, in order [default: all of them]
+        #[arg(value_parser = sketch_names())]
+        sketches: Vec<String>,
+        /// Seconds to show each sketch for
+        #[arg(long, default_value_t = 30.)]
+        duration: f32,
+        /// Seconds to cross-fade from one sketch to the next
+        #[arg(long, default_value_t = 2.)]
+        fade: f32,
+        /// Start each sketch with one of its presets, picked at random
+        #[arg(long)]
+        presets: bool,
+        /// Seed that the seed of each sketch is picked with [default: random]
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Change the settings of a sketch started with run --control, and print them
+    Control {
+        port: u16,
+        /// Settings to change, like separation=2.5 or rules/cohesion=0.1, with values as JSON
+        #[arg(value_name = "PARAMETER=VALUE", value_parser = parse_assignment)]
+        assignments: Vec<(String, Value)>,
+        /// Keep printing the settings whenever they change
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Export the shapes of a sketch as an SVG, for printing or plotting
+    Svg {
+        #[arg(value_parser = sketch_names())]
+        sketch: String,
+        #[arg(long, default_value_t = 800)]
+        width: u32,
+        #[arg(long, default_value_t = 600)]
+        height: u32,
+        /// Number of frames to run the sketch for before exporting
+        #[arg(long, default_value_t = 1)]
+        frames: u32,
+        /// Leave out the background rectangle
+        #[arg(long)]
+        no_background: bool,
+        /// [default: renders/<SKETCH>.svg]
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Seed for everything the sketch randomises [default: random]
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+}
+
+fn sketch_names() -> PossibleValuesParser {
+    PossibleValuesParser::new(registry::SKETCHES.iter().map(|sketch| sketch.name))
+}
+
+/** Split PARAMETER=VALUE, taking values that aren't valid JSON as strings */
+fn parse_assignment(assignment: &str) -> Result<(String, Value), String> {
+    let (parameter, value) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("{assignment} should look like PARAMETER=VALUE"))?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+    Ok((parameter.to_string(), value))
+}
+
+fn load_timeline(path: &Path) -> Timeline {
+    Timeline::load(path).unwrap_or_else(|error| {
+        eprintln!("Failed to read {}: {error}", path.display());
+        process::exit(1);
+    })
+}
+
+/** Use the given seed, or pick one and print it so that the run can be repeated */
+fn seed_or_random(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| {
+        let seed = random();
+        println!("Seed: {seed}");
+        seed
+    })
 }
 
 fn main() {
-    nannou::app(Model::new).update(update).run();
+    match Cli::parse().command {
+        Command::List => {
+            for sketch in registry::SKETCHES {
+                println!("{:<24} {}", sketch.name, sketch.description);
+            }
+        }
+        Command::Run {
+            sketch,
+            record_frames,
+            record_format,
+            record_out,
+            record_input,
+            control,
+            perf,
+            perf_log,
+            timeline,
+            seed,
+        } => {
+            let sketch = registry::find(&sketch).expect("Sketch name should be validated by clap");
+            (sketch.run)(RunOptions {
+                name: sketch.name,
+                seed: seed_or_random(seed),
+                record: RecordOptions {
+                    frames: record_frames,
+                    format: record_format,
+                    out: record_out
+                        .unwrap_or_else(|| PathBuf::from("recordings").join(sketch.name)),
+                },
+                record_input,
+                replay: None,
+                control,
+                perf,
+                perf_log,
+                timeline: timeline.as_deref().map(load_timeline),
+            });
+        }
+        Command::Replay {
+            file,
+            headless,
+            perf_log,
+            frames,
+            format,
+            out,
+        } => {
+            let session = Session::load(&file).unwrap_or_else(|error| {
+                eprintln!("Failed to read {}: {error}", file.display());
+                process::exit(1);
+            });
+            let Some(sketch) = registry::find(&session.sketch) else {
+                eprintln!(
+                    "{} is input for {}, which isn't a sketch",
+                    file.display(),
+                    session.sketch
+                );
+                process::exit(1);
+            };
+
+            if !headless {
+                (sketch.run)(RunOptions {
+                    name: sketch.name,
+                    seed: session.seed,
+                    record: RecordOptions {
+                        frames: 120,
+                        format: Format::Gif,
+                        out: PathBuf::from("recordings").join(sketch.name),
+                    },
+                    record_input: None,
+                    replay: Some(session),
+                    control: None,
+                    perf: perf_log.is_some(),
+                    perf_log,
+                    timeline: None,
+                });
+                return;
+            }
+            let [width, height] = session.window;
+            let options = RenderOptions {
+                width: width as u32,
+                height: height as u32,
+                frames: frames.unwrap_or(session.frames.len() as u32),
+                format,
+                seed: session.seed,
+                out: out.unwrap_or_else(|| {
+                    PathBuf::from("renders").join(format!("{}-replay", sketch.name))
+                }),
+                replay: Some(session),
+                timeline: None,
+            };
+            if let Err(error) = (sketch.render)(&options) {
+                eprintln!("Failed to render {}: {error}", sketch.name);
+                process::exit(1);
+            }
+        }
+        Command::Gallery {
+            sketches,
+            duration,
+            fade,
+            presets,
+            seed,
+        } => {
+            let sketches = if sketches.is_empty() {
+                registry::SKETCHES.iter().collect()
+            } else {
+                sketches
+                    .iter()
+                    .map(|name| {
+                        registry::find(name).expect("Sketch name should be validated by clap")
+                    })
+                    .collect()
+            };
+            gallery::run(GalleryOptions {
+                sketches,
+                duration,
+                fade,
+                presets,
+                seed: seed_or_random(seed),
+            });
+        }
+        Command::Render {
+            sketch,
+            width,
+            height,
+            frames,
+            format,
+            out,
+            timeline,
+            seed,
+        } => {
+            let sketch = registry::find(&sketch).expect("Sketch name should be validated by clap");
+            let options = RenderOptions {
+                width,
+                height,
+                frames,
+                format,
+                seed: seed_or_random(seed),
+                out: out.unwrap_or_else(|| PathBuf::from("renders").join(sketch.name)),
+                replay: None,
+                timeline: timeline.as_deref().map(load_timeline),
+            };
+            if let Err(error) = (sketch.render)(&options) {
+                eprintln!("Failed to render {}: {error}", sketch.name);
+                process::exit(1);
+            }
+        }
+        Command::Control {
+            port,
+            assignments,
+            watch,
+        } => {
+            if let Err(error) = control::client(port, &assignments, watch) {
+                eprintln!("Failed to control the sketch on port {port}: {error}");
+                process::exit(1);
+            }
+        }
+        Command::Svg {
+            sketch,
+            width,
+            height,
+            frames,
+            no_background,
+            out,
+            seed,
+        } => {
+            let sketch = registry::find(&sketch).expect("Sketch name should be validated by clap");
+            let options = SvgOptions {
+                width,
+                height,
+                frames,
+                background: !no_background,
+                seed: seed_or_random(seed),
+                out: out.unwrap_or_else(|| {
+                    PathBuf::from("renders")
+                        .join(sketch.name)
+                        .with_extension("svg")
+                }),
+            };
+            if let Err(error) = (sketch.export_svg)(&options) {
+                eprintln!("Failed to export {}: {error}", sketch.name);
+                process::exit(1);
+            }
+        }
+    }
 }
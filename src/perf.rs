@@ -0,0 +1,161 @@
+use crate::presets::capitalise;
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+/** Frames that the overlay averages over, so that its numbers can be read */
+const WINDOW: usize = 60;
+
+/** How long one frame took, and what it drew */
+#[derive(Clone, Copy, Debug, Default)]
+struct FrameTimes {
+    frame: Duration,
+    update: Duration,
+    view: Duration,
+    primitives: usize,
+}
+
+/**
+Frame, update and view times of a sketch, with the counters it reports through Sketch::counters.
+Averages the last second or so for the overlay, and can log every frame to a CSV file.
+*/
+pub struct Perf {
+    frames: VecDeque<FrameTimes>,
+    /** Filled in by view, which only has shared access to the host, and read by the next update */
+    last_view: Cell<(Duration, usize)>,
+    log: Option<PerfLog>,
+}
+
+impl Perf {
+    pub fn new(log: Option<PerfLog>) -> Self {
+        Perf {
+            frames: VecDeque::with_capacity(WINDOW),
+            last_view: Cell::default(),
+            log,
+        }
+    }
+
+    /** Note how long drawing took and how many shapes were drawn */
+    pub fn record_view(&self, time: Duration, primitives: usize) {
+        self.last_view.set((time, primitives));
+    }
+
+    /** Note how long the last frame and its update took, along with the latest view */
+    pub fn record_frame(&mut self, frame: Duration, update: Duration, counters: &[(&str, f64)]) {
+        let (view, primitives) = self.last_view.get();
+        let times = FrameTimes {
+            frame,
+            update,
+            view,
+            primitives,
+        };
+        if self.frames.len() == WINDOW {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(times);
+
+        if let Some(log) = &mut self.log {
+            if let Err(error) = log.write(times, counters) {
+                eprintln!("Failed to log performance, stopped logging: {error}");
+                self.log = None;
+            }
+        }
+    }
+
+    /** Lines for the overlay, averaged over the last frames */
+    pub fn summary(&self, counters: &[(&str, f64)]) -> Vec<(String, String)> {
+        let count = self.frames.len().max(1) as u32;
+        let average = |time: fn(&FrameTimes) -> Duration| {
+            self.frames.iter().map(time).sum::<Duration>() / count
+        };
+        let frame = average(|times| times.frame);
+        let fps = if frame.is_zero() {
+            0.
+        } else {
+            1. / frame.as_secs_f64()
+        };
+        let update = average(|times| times.update);
+        let view = average(|times| times.view);
+        let primitives = self.frames.back().map_or(0, |times| times.primitives);
+
+        let mut lines = vec![
+            ("FPS".to_string(), format!("{fps:.1}")),
+            ("Frame".to_string(), milliseconds(frame)),
+            ("Update".to_string(), milliseconds(update)),
+            ("View".to_string(), milliseconds(view)),
+            ("Primitives".to_string(), primitives.to_string()),
+        ];
+        lines.extend(
+            counters
+                .iter()
+                .map(|(name, value)| (capitalise(name), value.to_string())),
+        );
+        lines
+    }
+}
+
+fn milliseconds(time: Duration) -> String {
+    format!("{:.2} ms", time.as_secs_f64() * 1000.)
+}
+
+/**
+Writes a row per frame to a CSV file, for comparing runs before and after an optimisation.
+Times are in milliseconds, and the sketch's counters get a column each after the times.
+*/
+pub struct PerfLog {
+    writer: BufWriter<File>,
+    frame: u64,
+    /** Names of the counters, written as the header once the first frame is logged */
+    counters: Option<Vec<String>>,
+}
+
+impl PerfLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(PerfLog {
+            writer: BufWriter::new(File::create(path)?),
+            frame: 0,
+            counters: None,
+        })
+    }
+
+    fn write(&mut self, times: FrameTimes, counters: &[(&str, f64)]) -> io::Result<()> {
+        if self.counters.is_none() {
+            let names: Vec<String> = counters.iter().map(|(name, _)| name.to_string()).collect();
+            write!(self.writer, "frame,frame_ms,update_ms,view_ms,primitives")?;
+            for name in &names {
+                write!(self.writer, ",{}", name.replace([',', '"'], ""))?;
+            }
+            writeln!(self.writer)?;
+            self.counters = Some(names);
+        }
+
+        let ms = |time: Duration| time.as_secs_f64() * 1000.;
+        write!(
+            self.writer,
+            "{},{:.3},{:.3},{:.3},{}",
+            self.frame,
+            ms(times.frame),
+            ms(times.update),
+            ms(times.view),
+            times.primitives
+        )?;
+        // Counters are matched to the header by name, in case a sketch reports them in another order
+        for name in self.counters.iter().flatten() {
+            match counters.iter().find(|(counter, _)| counter == name) {
+                Some((_, value)) => write!(self.writer, ",{value}")?,
+                None => write!(self.writer, ",")?,
+            }
+        }
+        writeln!(self.writer)?;
+        self.frame += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use crate::sketch::Context;
+use nannou_egui::egui::{self, Ui};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/** Extensions that presets can be loaded from, new presets are saved as the first */
+const EXTENSIONS: [&str; 2] = ["toml", "json"];
+
+#[derive(Debug)]
+pub enum PresetError {
+    Io(io::Error),
+    ReadToml(toml::de::Error),
+    WriteToml(toml::ser::Error),
+    Json(serde_json::Error),
+    UnknownFormat(PathBuf),
+    /** Settings that were read but can't be used, with why */
+    Invalid(String),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PresetError::Io(error) => write!(f, "{error}"),
+            PresetError::ReadToml(error) => write!(f, "invalid TOML: {error}"),
+            PresetError::WriteToml(error) => write!(f, "can't write as TOML: {error}"),
+            PresetError::Json(error) => write!(f, "invalid JSON: {error}"),
+            PresetError::UnknownFormat(path) => {
+                write!(f, "{} is not a .toml or .json file", path.display())
+            }
+            PresetError::Invalid(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl Error for PresetError {}
+
+impl From<io::Error> for PresetError {
+    fn from(error: io::Error) -> Self {
+        PresetError::Io(error)
+    }
+}
+
+/** Read settings from a TOML or JSON file, going by its extension */
+pub fn load<T: DeserializeOwned>(path: &Path) -> Result<T, PresetError> {
+    let contents = fs::read_to_string(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(PresetError::ReadToml),
+        Some("json") => serde_json::from_str(&contents).map_err(PresetError::Json),
+        _ => Err(PresetError::UnknownFormat(path.to_path_buf())),
+    }
+}
+
+/** Write settings to a TOML or JSON file, going by its extension */
+pub fn save<T: Serialize>(path: &Path, settings: &T) -> Result<(), PresetError> {
+    let contents = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::to_string_pretty(settings).map_err(PresetError::WriteToml)?,
+        Some("json") => serde_json::to_string_pretty(settings).map_err(PresetError::Json)?,
+        _ => return Err(PresetError::UnknownFormat(path.to_path_buf())),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/**
+A library of named settings for one sketch, kept as files in assets/presets/<sketch>.
+Shows a picker to load them and a box to save the current settings as a new one.
+*/
+pub struct Presets {
+    folder: PathBuf,
+    /** What the files are called in the picker, like preset */
+    noun: &'static str,
+    /** File names of the presets in the folder, sorted */
+    files: Vec<String>,
+    selected: Option<String>,
+    new_name: String,
+    /** Result of the last load or save, shown under the picker */
+    message: Option<String>,
+}
+
+/** Where the presets of a sketch are kept */
+pub fn folder(ctx: &Context, sketch: &str) -> PathBuf {
+    ctx.assets_path().join("presets").join(sketch)
+}
+
+/** Every TOML or JSON file in a folder of presets, sorted, or none if the folder doesn't exist */
+pub fn files(folder: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return vec![];
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| EXTENSIONS.contains(&extension))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+impl Presets {
+    pub fn new(ctx: &Context, sketch: &str) -> Self {
+        Presets::in_folder(folder(ctx, sketch), "preset")
+    }
+
+    /** A library of something other than settings, like timelines, in a folder of its own */
+    pub fn in_folder(folder: PathBuf, noun: &'static str) -> Self {
+        let mut presets = Presets {
+            folder,
+            noun,
+            files: vec![],
+            selected: None,
+            new_name: String::new(),
+            message: None,
+        };
+        presets.refresh();
+        presets
+    }
+
+    /** Look for presets again, as they may have been added outside of the sketch */
+    fn refresh(&mut self) {
+        self.files = files(&self.folder)
+            .iter()
+            .filter_map(|path| Some(path.file_name()?.to_str()?.to_string()))
+            .collect();
+    }
+
+    /**
+    Show the preset picker, saving settings when asked to.
+    Returns the settings of a preset when one is loaded, for the sketch to apply.
+    */
+    pub fn ui<T: Serialize + DeserializeOwned>(&mut self, ui: &mut Ui, settings: &T) -> Option<T> {
+        let mut loaded = None;
+
+        let noun = self.noun;
+        ui.separator();
+        ui.label(format!("{}s:", capitalise(noun)));
+        let combo_box = egui::ComboBox::from_id_source(noun)
+            .selected_text(
+                self.selected
+                    .clone()
+                    .unwrap_or_else(|| format!("Choose a {noun}")),
+            )
+            .show_ui(ui, |ui| {
+                for file in &self.files {
+                    ui.selectable_value(&mut self.selected, Some(file.clone()), file);
+                }
+            });
+        if combo_box.response.clicked() {
+            self.refresh();
+        }
+
+        let load_clicked = ui
+            .add_enabled(
+                self.selected.is_some(),
+                egui::Button::new(format!("Load {noun}")),
+            )
+            .clicked();
+        if let (true, Some(file)) = (load_clicked, &self.selected) {
+            match load(&self.folder.join(file)) {
+                Ok(settings) => {
+                    loaded = Some(settings);
+                    self.message = Some(format!("Loaded {file}"));
+                }
+                Err(error) => self.message = Some(format!("Failed to load {file}: {error}")),
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_name);
+            let save_clicked = ui
+                .add_enabled(!self.new_name.trim().is_empty(), egui::Button::new("Save"))
+                .clicked();
+            if save_clicked {
+                let file = format!("{}.{}", self.new_name.trim(), EXTENSIONS[0]);
+                let saved = check_name(self.new_name.trim())
+                    .and_then(|()| save(&self.folder.join(&file), settings));
+                match saved {
+                    Ok(()) => {
+                        self.message = Some(format!("Saved {file}"));
+                        self.refresh();
+                        self.selected = Some(file);
+                        self.new_name.clear();
+                    }
+                    Err(error) => self.message = Some(format!("Failed to save {file}: {error}")),
+                }
+            }
+        });
+
+        if let Some(message) = &self.message {
+            ui.label(message);
+        }
+        loaded
+    }
+}
+
+/** Names are joined onto the folder, so they can't lead out of it or make hidden files */
+fn check_name(name: &str) -> Result<(), PresetError> {
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(PresetError::Invalid(
+            "names can't contain / or \\, or start with .".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/** Upper case the first letter, for names shown at the start of a label */
+pub(crate) fn capitalise(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -1,293 +1,233 @@
-use crate::geometry::{angle_between_points, Polygon};
-use nannou::prelude::*;
-use nannou_egui::{
-    egui::{self, Ui},
-    Egui,
+use crate::{
+    canvas::Canvas,
+    clock::SimClock,
+    config::ConfigFile,
+    geometry::{angle_between_points, Polygon},
+    presets::{PresetError, Presets},
+    settings_panel::{Change, Fields, Mode, PanelSettings, SettingsPanel},
+    simulations::boids::{self, Boid, Rules},
+    sketch::{Context, Sketch, WindowOptions},
 };
+use nannou::{prelude::*, rand::rngs::StdRng};
+use nannou_egui::egui::Ui;
+use serde::{Deserialize, Serialize};
+
+/** Constants that are read from assets/config/boids.toml, and reloaded when it changes */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    max_speed: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { max_speed: 15. }
+    }
+}
 
-const MAX_SPEED: f32 = 15.;
+/** Boids move by their velocity this many times a second */
+const STEPS_PER_SECOND: f32 = 60.;
 
 pub struct Model {
     boids: Vec<Boid>,
-    settings: Settings,
-    unapplied_settings: Settings,
-    egui: Egui,
+    settings: SettingsPanel<Settings>,
+    /** Moves the boids one step at a time, starting paused */
+    clock: SimClock,
+    rng: StdRng,
+    presets: Presets,
+    config: ConfigFile<Config>,
 }
 
 impl Model {
-    pub fn new(app: &App) -> Self {
-        let window_id = app
-            .new_window()
-            .view(view)
-            .fullscreen()
-            .raw_event(raw_window_event)
-            .build()
-            .unwrap();
+    fn new_boids(window: &Rect, rng: &mut StdRng) -> Vec<Boid> {
+        boids::new_flock(10, window, rng)
+    }
 
-        Model {
-            boids: Model::new_boids(&app.window_rect()),
-            settings: Settings::new(),
-            unapplied_settings: Settings::new(),
-            egui: Egui::from_window(&app.window(window_id).unwrap()),
+    /** Boids start again when settings are applied or loaded, but carry on when they are edited live */
+    fn settings_changed(&mut self, ctx: &Context, change: Change) {
+        if change != Change::Edited {
+            self.boids = Model::new_boids(&ctx.window_rect(), &mut self.rng);
         }
     }
 
-    fn new_boids(window: &Rect) -> Vec<Boid> {
-        (0..10).map(|_| Boid::new_random(window)).collect()
+    fn rules(&self) -> Rules {
+        let settings = self.settings.settings();
+        Rules {
+            visual_range: settings.visual_range,
+            containment: settings.containment,
+            cohesion: settings.cohesion,
+            separation: settings.separation,
+            alignment: settings.alignment,
+            max_speed: self.config.max_speed,
+        }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 struct Settings {
     visual_range: f32,
     containment: f32,
     cohesion: f32,
     separation: f32,
     alignment: f32,
-    paused: bool,
 }
 
-impl Settings {
-    fn new() -> Self {
+impl Default for Settings {
+    fn default() -> Self {
         Settings {
             visual_range: 100.,
             containment: 5.,
             cohesion: 1.,
             separation: 1.,
             alignment: 1.,
-            paused: true,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-struct Boid {
-    position: Point2,
-    velocity: Vec2,
-}
-
-impl Boid {
-    fn new_random(boundary: &Rect) -> Self {
-        let position = pt2(
-            random_range(boundary.x.start, boundary.x.end),
-            random_range(boundary.y.start, boundary.y.end),
-        );
-
-        let angle = random_range(0., 2. * PI);
-
-        Boid {
-            position,
-            velocity: vec2(angle.cos(), angle.sin()).normalize(),
-        }
+impl PanelSettings for Settings {
+    fn fields(fields: &mut Fields<Self>) {
+        fields
+            .slider(
+                "Visual range of boids:",
+                |settings| &mut settings.visual_range,
+                10. ..=500.,
+            )
+            .tooltip("How far away other boids can be and still affect a boid");
+        fields
+            .slider(
+                "Containment factor:",
+                |settings| &mut settings.containment,
+                0.1..=20.,
+            )
+            .tooltip("How hard boids turn back when they reach the edge of the window");
+        fields
+            .slider(
+                "Cohesion factor:",
+                |settings| &mut settings.cohesion,
+                0.1..=10.,
+            )
+            .tooltip("How strongly boids steer towards the centre of their neighbours");
+        fields
+            .slider(
+                "Separation factor:",
+                |settings| &mut settings.separation,
+                0.1..=10.,
+            )
+            .tooltip("How strongly boids steer away from neighbours that are too close");
+        fields
+            .slider(
+                "Alignment factor:",
+                |settings| &mut settings.alignment,
+                0.1..=10.,
+            )
+            .tooltip("How strongly boids match the velocity of their neighbours");
     }
 }
 
-fn raw_window_event(_app: &App, model: &mut Model, event: &nannou::winit::event::WindowEvent) {
-    // Let egui handle things like keyboard and mouse input.
-    model.egui.handle_raw_event(event);
-}
-
-fn create_ui(
-    ui: &mut Ui,
-    window: &Rect,
-    boids: &mut Vec<Boid>,
-    settings: &mut Settings,
-    unapplied_settings: &mut Settings,
-) {
-    ui.label("Visual range of boids:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.visual_range,
-        (10.)..=(500.),
-    ));
+impl Sketch for Model {
+    const HAS_SETTINGS: bool = true;
 
-    ui.label("Containment factor:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.containment,
-        0.1..=20.,
-    ));
-
-    ui.label("Cohesion factor:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.cohesion,
-        0.1..=10.,
-    ));
-
-    ui.label("Separation factor:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.separation,
-        0.1..=10.,
-    ));
-
-    ui.label("Alignment factor:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.alignment,
-        0.1..=10.,
-    ));
-
-    let apply = ui.button("Apply changes").clicked();
-    let reset = ui.button("Reset changes").clicked();
-    let toggle_pause = ui
-        .button(if settings.paused { "Resume" } else { "Pause" })
-        .clicked();
-
-    if apply {
-        *settings = *unapplied_settings;
-        *boids = Model::new_boids(window);
-    } else if reset {
-        *settings = Settings::new();
-        *boids = Model::new_boids(window);
-        *unapplied_settings = *settings;
-    } else if toggle_pause {
-        settings.paused = !settings.paused;
+    fn window() -> WindowOptions {
+        WindowOptions {
+            fullscreen: true,
+            ..Default::default()
+        }
     }
-}
 
-pub fn update(app: &App, model: &mut Model, update: Update) {
-    model.egui.set_elapsed_time(update.since_start);
-    let ctx = model.egui.begin_frame();
-
-    egui::Window::new("Settings").show(&ctx, |ui| {
-        create_ui(
-            ui,
-            &app.window_rect(),
-            &mut model.boids,
-            &mut model.settings,
-            &mut model.unapplied_settings,
-        );
-    });
-
-    if model.settings.paused {
-        return;
+    fn init(ctx: &Context) -> Self {
+        let mut rng = ctx.rng();
+        Model {
+            boids: Model::new_boids(&ctx.window_rect(), &mut rng),
+            settings: SettingsPanel::new(Mode::Apply),
+            clock: {
+                let mut clock = SimClock::new(STEPS_PER_SECOND);
+                clock.set_paused(true);
+                clock
+            },
+            rng,
+            presets: Presets::new(ctx, "boids"),
+            config: ConfigFile::load(ctx, "boids"),
+        }
     }
 
-    let boids = model.boids.clone();
-    for boid in model.boids.iter_mut() {
-        containment(boid, app.window_rect(), model.settings.containment);
-        cohesion(boid, &boids, model.settings);
-        separation(boid, &boids, model.settings.separation);
-        alignment(boid, &boids, model.settings);
-
-        if boid.velocity.length() > MAX_SPEED {
-            boid.velocity = boid.velocity.normalize() * MAX_SPEED;
+    fn update(&mut self, ctx: &Context, dt: f32) {
+        self.config.reload_if_changed();
+        let rules = self.rules();
+        for _ in 0..self.clock.advance(dt) {
+            boids::step(&mut self.boids, ctx.window_rect(), &rules);
         }
-        boid.position += boid.velocity
     }
-}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-    draw.background().color(BLACK);
-
-    for boid in &model.boids {
-        let mut tri = Polygon {
-            points: vec![pt2(10., 0.), pt2(-5., 5.), pt2(-5., -5.)],
-        };
-        tri.translate(boid.position);
-        let angle = angle_between_points(boid.velocity, pt2(0., 0.), pt2(1., 0.));
-
-        if boid.velocity.y > 0. {
-            if boid.velocity.x == 0. {
-                tri.rotate(2. * PI - angle);
-            } else {
-                tri.rotate(angle);
-            }
-        } else {
-            if boid.velocity.x == 0. {
+    fn draw(&self, _ctx: &Context, canvas: &mut Canvas) {
+        canvas.background(BLACK);
+
+        for boid in &self.boids {
+            let mut tri = Polygon {
+                points: vec![pt2(10., 0.), pt2(-5., 5.), pt2(-5., -5.)],
+            };
+            tri.translate(boid.position);
+            let angle = angle_between_points(boid.velocity, pt2(0., 0.), pt2(1., 0.));
+
+            if boid.velocity.y > 0. {
+                if boid.velocity.x == 0. {
+                    tri.rotate(2. * PI - angle);
+                } else {
+                    tri.rotate(angle);
+                }
+            } else if boid.velocity.x == 0. {
                 tri.rotate(angle);
             } else {
                 tri.rotate(2. * PI - angle);
             }
-        }
 
-        draw.tri()
-            .points(tri.points[0], tri.points[1], tri.points[2]);
-        draw.line()
-            .points(tri.points[0], tri.points[0] + boid.velocity)
-            .color(RED);
-    }
-
-    // put everything on the frame
-    draw.to_frame(app, &frame).unwrap();
-    model.egui.draw_to_frame(&frame).unwrap();
-}
-
-fn containment(boid: &mut Boid, window: Rect, factor: f32) {
-    let margin = 10.;
-
-    if boid.position.x < window.x.start + margin {
-        boid.velocity.x += factor;
-    } else if boid.position.x > window.x.end - margin {
-        boid.velocity.x -= factor
-    }
-    if boid.position.y < window.y.start + margin {
-        boid.velocity.y += factor;
-    } else if boid.position.y > window.y.end - margin {
-        boid.velocity.y -= factor;
+            canvas.tri(tri.points[0], tri.points[1], tri.points[2]);
+            canvas
+                .line(tri.points[0], tri.points[0] + boid.velocity)
+                .color(RED);
+        }
     }
-}
 
-/** Find the center of mass of the other boids and adjust velocity slightly to point towards the center of mass. */
-fn cohesion(boid: &mut Boid, boids: &Vec<Boid>, settings: Settings) {
-    let mut center_position = pt2(0., 0.);
-    let mut num_neighbors = 0;
-
-    for other_boid in boids {
-        if boid == other_boid {
-            continue;
+    fn settings_ui(&mut self, ctx: &Context, ui: &mut Ui) -> Option<Change> {
+        let mut change = self.settings.ui(ui);
+        if let Some(settings) = self.presets.ui(ui, self.settings.settings()) {
+            self.settings.set(settings);
+            change = Some(Change::Loaded);
         }
-        if boid.position.distance(other_boid.position) > settings.visual_range {
-            continue;
+        if let Some(change) = change {
+            self.settings_changed(ctx, change);
         }
-        center_position += other_boid.position;
-        num_neighbors += 1;
+        change
     }
 
-    if num_neighbors == 0 {
-        return;
+    fn settings(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self.settings.settings()).ok()
     }
-    center_position /= num_neighbors as f32;
-    boid.velocity += (center_position - boid.position) * settings.cohesion;
-}
 
-/** Move away from other boids that are too close to avoid colliding */
-fn separation(boid: &mut Boid, boids: &Vec<Boid>, factor: f32) {
-    let min_distance = 20.;
-
-    let mut steer = vec2(0., 0.);
-    for other_boid in boids {
-        if boid == other_boid {
-            continue;
-        }
-        if boid.position.distance(other_boid.position) > min_distance {
-            continue; // if boid and other_boid are far enough apart
-        }
-        steer -= other_boid.position - boid.position;
+    fn apply_settings(
+        &mut self,
+        ctx: &Context,
+        change: Change,
+        values: serde_json::Value,
+    ) -> Result<(), PresetError> {
+        self.settings
+            .set(serde_json::from_value(values).map_err(PresetError::Json)?);
+        self.settings_changed(ctx, change);
+        Ok(())
     }
 
-    boid.velocity += steer * factor;
-}
-
-// Find the average velocity (speed and direction) of the other boids and
-// adjust velocity slightly to match.
-fn alignment(boid: &mut Boid, boids: &Vec<Boid>, settings: Settings) {
-    let mut avg_velocity = vec2(0., 0.);
-    let mut num_neighbours = 0;
+    fn counters(&self) -> Vec<(&'static str, f64)> {
+        vec![("boids", self.boids.len() as f64)]
+    }
 
-    for other_boid in boids {
-        if boid == other_boid {
-            continue;
-        }
-        if boid.position.distance(other_boid.position) > settings.visual_range {
-            continue;
-        }
-        avg_velocity += other_boid.velocity;
-        num_neighbours += 1;
+    fn autoplay(&mut self, _ctx: &Context) {
+        self.clock.set_paused(false);
     }
 
-    if num_neighbours == 0 {
-        return;
+    fn clock(&mut self) -> Option<&mut SimClock> {
+        Some(&mut self.clock)
     }
-    avg_velocity /= num_neighbours as f32;
 
-    boid.velocity += (avg_velocity - boid.velocity) * settings.alignment;
+    fn error(&self) -> Option<&str> {
+        self.config.error()
+    }
 }
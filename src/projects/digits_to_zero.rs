@@ -26,7 +26,7 @@ pub fn is_n_zeroable(n: i32) -> bool {
         .collect();
 
     // check if n has a 0
-    if digits.iter().any(|&digit| digit == 0) {
+    if digits.contains(&0) {
         return true;
     }
 
@@ -36,13 +36,9 @@ pub fn is_n_zeroable(n: i32) -> bool {
         return true;
     }
 
-    return recursive_combinations(digits);
+    recursive_combinations(digits)
 }
 
 fn recursive_combinations(digits: Vec<u32>) -> bool {
-    if digits.len() <= 3 {
-        return true;
-    } else {
-        return false;
-    }
+    digits.len() <= 3
 }
@@ -1,130 +1,146 @@
+use crate::{
+    canvas::Canvas,
+    config::ConfigFile,
+    presets::{PresetError, Presets},
+    settings_panel::{Change, Fields, Mode, PanelSettings, SettingsPanel},
+    simulations::distance_between_points::{color_points, new_points, Functions},
+    sketch::{Context, Sketch},
+};
 use nannou::prelude::*;
-use nannou_egui::{egui, Egui};
+use nannou_egui::egui::Ui;
+use serde::{Deserialize, Serialize};
 
 pub struct Model {
     points: Vec<(Point2, f32)>,
-    selected_function: Functions,
-    egui: Egui,
+    settings: SettingsPanel<Settings>,
+    presets: Presets,
+    config: ConfigFile<Config>,
 }
 
-const POINT_WIDTH: f32 = 5.;
-const GREYSCALE: bool = false;
-
-#[derive(PartialEq)]
-enum Functions {
-    Power,
-    Rational,
-    Log,
-    Sqrt,
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Settings {
+    function: Functions,
 }
 
-fn some_math_function(x: f32, function_name: &Functions) -> f32 {
-    match function_name {
-        Functions::Power => x.powi(2),
-        Functions::Rational => 1. / x,
-        Functions::Log => x.ln(),
-        Functions::Sqrt => x.sqrt(),
+impl PanelSettings for Settings {
+    fn fields(fields: &mut Fields<Self>) {
+        fields.choice(
+            "Function to use",
+            |settings| &mut settings.function,
+            &[
+                (Functions::Power, "x^2"),
+                (Functions::Rational, "1/x"),
+                (Functions::Log, "ln(x)"),
+                (Functions::Sqrt, "sqrt(x)"),
+            ],
+        );
     }
 }
 
-pub fn model(app: &App) -> Model {
-    let window_id = app
-        .new_window()
-        .raw_event(raw_window_event)
-        .view(view)
-        .build()
-        .unwrap();
-    let window = app.window_rect();
-
-    let mut points: Vec<(Vec2, f32)> = vec![];
-    let mut current_point = pt2(window.x.start, window.y.end);
-
-    while current_point.y > window.y.start {
-        points.push((current_point, 0.));
-
-        if current_point[0] + POINT_WIDTH * 1.5 > window.x.end {
-            current_point[0] = window.x.start;
-            current_point[1] -= POINT_WIDTH;
-        } else {
-            current_point[0] += POINT_WIDTH;
-        }
-    }
+/** Constants that are read from assets/config/distance_between_points.toml, and reloaded when it changes */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /** Size of the squares the window is coloured in */
+    point_width: f32,
+}
 
-    color_points(&mut points, &Functions::Power);
-    Model {
-        points,
-        selected_function: Functions::Power,
-        egui: Egui::from_window(&app.window(window_id).unwrap()),
+impl Default for Config {
+    fn default() -> Self {
+        Config { point_width: 5. }
     }
 }
 
-fn raw_window_event(_app: &App, model: &mut Model, event: &nannou::winit::event::WindowEvent) {
-    // Let egui handle things like keyboard and mouse input.
-    model.egui.handle_raw_event(event);
+const GREYSCALE: bool = false;
+/** Points are shaded in grey from 0 to 1, or in hues from 0 to 255 */
+const MAX_SHADE: f32 = if GREYSCALE { 1. } else { 255. };
+
+impl Model {
+    fn settings_changed(&mut self, _ctx: &Context, _change: Change) {
+        color_points(
+            &mut self.points,
+            &self.settings.settings().function,
+            MAX_SHADE,
+        );
+    }
 }
 
-pub(crate) fn update(_app: &App, model: &mut Model, update: Update) {
-    model.egui.set_elapsed_time(update.since_start);
-    let ctx = model.egui.begin_frame();
+impl Sketch for Model {
+    const HAS_SETTINGS: bool = true;
 
-    egui::Window::new("Settings").show(&ctx, |ui| {
-        ui.label("Function to use");
-        ui.radio_value(&mut model.selected_function, Functions::Power, "x^2");
-        ui.radio_value(&mut model.selected_function, Functions::Rational, "1/x");
-        ui.radio_value(&mut model.selected_function, Functions::Log, "ln(x)");
-        ui.radio_value(&mut model.selected_function, Functions::Sqrt, "sqrt(x)");
+    fn init(ctx: &Context) -> Self {
+        let config = ConfigFile::<Config>::load(ctx, "distance_between_points");
+        let mut points = new_points(ctx.window_rect(), config.point_width);
 
-        let changed = ui.button("Change function").clicked();
-        if changed {
-            color_points(&mut model.points, &model.selected_function);
+        let settings = SettingsPanel::<Settings>::new(Mode::Apply);
+        color_points(&mut points, &settings.settings().function, MAX_SHADE);
+        Model {
+            points,
+            settings,
+            presets: Presets::new(ctx, "distance_between_points"),
+            config,
         }
-    });
-}
+    }
 
-fn color_points(points: &mut Vec<(Vec2, f32)>, function_name: &Functions) {
-    let mut max_distance: f32 = 0.;
-    let mut min_distance: f32 = 99999.;
-    for (point, value) in points {
-        let distance = pt2(point.x, some_math_function(point.x, &function_name))
-            .distance(pt2(point.y, some_math_function(point.y, &function_name)));
-
-        if max_distance < distance {
-            max_distance = distance;
-        } else if min_distance > distance {
-            min_distance = distance;
+    fn update(&mut self, ctx: &Context, _dt: f32) {
+        if self.config.reload_if_changed() {
+            self.points = new_points(ctx.window_rect(), self.config.point_width);
+            color_points(
+                &mut self.points,
+                &self.settings.settings().function,
+                MAX_SHADE,
+            );
+        }
+    }
+
+    fn draw(&self, _ctx: &Context, canvas: &mut Canvas) {
+        let point_width = self.config.point_width;
+        for point in &self.points {
+            if GREYSCALE {
+                canvas
+                    .rect(Rect::from_xy_wh(point.0, vec2(point_width, point_width)))
+                    .rgb(point.1, point.1, point.1);
+            } else {
+                canvas
+                    .rect(Rect::from_xy_wh(point.0, vec2(point_width, point_width)))
+                    .hsv(point.1, 50., 50.);
+            }
         }
-        *value = clamp(
-            map_range(
-                distance,
-                min_distance,
-                max_distance,
-                0.,
-                if GREYSCALE { 1. } else { 255. },
-            ),
-            0.,
-            if GREYSCALE { 1. } else { 255. },
-        );
     }
-}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-
-    for point in &model.points {
-        if GREYSCALE {
-            draw.rect()
-                .xy(point.0)
-                .wh(vec2(POINT_WIDTH, POINT_WIDTH))
-                .rgb(point.1, point.1, point.1);
-        } else {
-            draw.rect()
-                .xy(point.0)
-                .wh(vec2(POINT_WIDTH, POINT_WIDTH))
-                .hsv(point.1, 50., 50.);
+    fn settings_ui(&mut self, _ctx: &Context, ui: &mut Ui) -> Option<Change> {
+        let mut change = self.settings.ui(ui);
+        if let Some(settings) = self.presets.ui(ui, self.settings.settings()) {
+            self.settings.set(settings);
+            change = Some(Change::Loaded);
+        }
+        if let Some(change) = change {
+            self.settings_changed(_ctx, change);
         }
+        change
+    }
+
+    fn settings(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self.settings.settings()).ok()
+    }
+
+    fn apply_settings(
+        &mut self,
+        _ctx: &Context,
+        change: Change,
+        values: serde_json::Value,
+    ) -> Result<(), PresetError> {
+        self.settings
+            .set(serde_json::from_value(values).map_err(PresetError::Json)?);
+        self.settings_changed(_ctx, change);
+        Ok(())
     }
 
-    // put everything on the frame
-    draw.to_frame(app, &frame).unwrap();
-    model.egui.draw_to_frame(&frame).unwrap();
+    fn counters(&self) -> Vec<(&'static str, f64)> {
+        vec![("points", self.points.len() as f64)]
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.config.error()
+    }
 }
@@ -1,94 +1,34 @@
-use crate::geometry::{angle_between_points, rotate_point, Direction, Polygon, NO_VERTICES_ERROR};
-use geom::bounding_rect;
-use nannou::prelude::*;
-use nannou_egui::{
-    egui::{self, Ui},
-    Egui,
+use crate::{
+    canvas::Canvas,
+    clock::SimClock,
+    config::ConfigFile,
+    geometry::Polygon,
+    presets::{PresetError, Presets},
+    settings_panel::{Change, Fields, Mode, PanelSettings, SettingsPanel},
+    simulations::epicyclogons::{resize, roll_to_collision, rotate_things, State},
+    sketch::{Context, Sketch},
 };
-use std::cmp::Ordering;
-
-const ROTATING_ANGLE: f32 = 0.5 * PI / 180.0;
-
-struct State {
-    fixed: Polygon,
-    rotating: Polygon,
-    // rotating_point is the point that rotating is rotating around
-    rotating_point: (Point2, usize),
-    // next_point is the vertex on the polygon that is next to touch the other polygon
-    next_point_fixed: (Point2, usize),
-    next_point_rotating: (Point2, usize),
-    traced_path: Vec<Point2>,
-    tracing_point: Point2,
-    collisions_num: u32,
+use nannou::prelude::*;
+use nannou_egui::egui::Ui;
+use serde::{Deserialize, Serialize};
+
+/** Constants that are read from assets/config/epicyclogons.toml, and reloaded when it changes */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /** Largest angle the rotating polygon turns by in one step, in degrees */
+    rotating_angle: f32,
 }
 
-impl State {
-    fn new(settings: &Settings) -> Self {
-        let fixed = Polygon::new(settings.fixed_radius, settings.fixed_sides);
-        let mut rotating = Polygon::new(settings.rotating_radius, settings.rotating_sides);
-
-        rotating.align(&fixed, Direction::Above);
-
-        let rotating_point;
-        let next_point_fixed;
-        let next_point_rotating;
-
-        let bounding_boxes = [
-            bounding_rect(fixed.points.clone()).unwrap(),
-            bounding_rect(rotating.points.clone()).unwrap(),
-        ];
-        if fixed.points.len() % 2 == 0 {
-            // if fixed is bigger than rotating
-            if bounding_boxes[0].w() > bounding_boxes[1].w() {
-                let bottom_right = rotating.points.len() / 4;
-                rotating_point = (rotating.points[bottom_right], bottom_right);
-                next_point_fixed = (fixed.points[0], 0);
-                if bottom_right == 0 {
-                    next_point_rotating = (
-                        rotating.points[rotating.points.len() - 1],
-                        rotating.points.len() - 1,
-                    );
-                } else {
-                    next_point_rotating = (rotating.points[bottom_right - 1], bottom_right - 1);
-                };
-            } else {
-                rotating_point = (fixed.points[0], 0);
-                next_point_fixed = (fixed.points[1], 1);
-                next_point_rotating = (
-                    rotating.points[rotating.points.len() / 4],
-                    rotating.points.len() / 4,
-                );
-            }
-        } else {
-            rotating_point = (fixed.points[fixed.points.len() - 1], fixed.points.len() - 1);
-            next_point_fixed = (fixed.points[0], 0);
-            next_point_rotating = (
-                rotating.points[rotating.points.len() / 4],
-                rotating.points.len() / 4,
-            );
-        }
-
-        let lowest_vertex_rotating = rotating
-            .points
-            .iter()
-            .min_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
-            .unwrap()
-            .clone();
-
-        State {
-            fixed,
-            rotating,
-            rotating_point,
-            next_point_fixed,
-            next_point_rotating,
-            traced_path: vec![],
-            tracing_point: pt2(0., lowest_vertex_rotating.y),
-            collisions_num: 0,
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            rotating_angle: 0.5,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Settings {
     fixed_radius: f32,
     rotating_radius: f32,
@@ -101,8 +41,8 @@ struct Settings {
     speed: u32,
 }
 
-impl Settings {
-    fn new() -> Self {
+impl Default for Settings {
+    fn default() -> Self {
         Settings {
             fixed_radius: 100.,
             rotating_radius: 100.,
@@ -114,182 +54,239 @@ impl Settings {
     }
 }
 
-pub struct Model {
-    state: State,
-    settings: Settings,
-    unapplied_settings: Settings,
-    egui: Egui,
-}
-
-impl Model {
-    pub fn new(app: &App) -> Self {
-        let window_id = app
-            .new_window()
-            .view(view)
-            .raw_event(raw_window_event)
-            .build()
-            .unwrap();
+impl Settings {
+    /**
+    Whether the rolling can carry on from where it is, as the polygons have the same shapes,
+    if perhaps different sizes, so speeding it up or growing it doesn't start it again
+    */
+    fn same_shapes(&self, other: &Settings) -> bool {
+        self.fixed_sides == other.fixed_sides
+            && self.rotating_sides == other.rotating_sides
+            && self.collisions_num == other.collisions_num
+    }
 
-        let settings = Settings::new();
-        let state = State::new(&settings);
+    /**
+    Settings can come from presets or the control server rather than the sliders,
+    and update loops over these counts, so ones past the sliders could freeze the window
+    */
+    fn check_counts(&self) -> Result<(), PresetError> {
+        if !(-1..=MAX_COLLISIONS).contains(&self.collisions_num) {
+            return Err(PresetError::Invalid(format!(
+                "collisions to do must be from -1 to {MAX_COLLISIONS}, not {}",
+                self.collisions_num
+            )));
+        }
+        if !(1..=MAX_SPEED).contains(&self.speed) {
+            return Err(PresetError::Invalid(format!(
+                "speed must be from 1 to {MAX_SPEED}, not {}",
+                self.speed
+            )));
+        }
+        Ok(())
+    }
 
-        Model {
-            state,
-            settings: settings.clone(),
-            unapplied_settings: settings.clone(),
-            egui: Egui::from_window(&*app.window(window_id).unwrap()),
+    /** Sides and radii can come from presets or the control server too, so the polygons are checked */
+    fn polygons(&self) -> Result<(Polygon, Polygon), PresetError> {
+        // Resizing scales by the ratio of the radii, which would turn a polygon inside out if it were negative
+        if !(self.fixed_radius > 0. && self.rotating_radius > 0.) {
+            return Err(PresetError::Invalid(format!(
+                "radii must be more than 0, not {} and {}",
+                self.fixed_radius, self.rotating_radius
+            )));
         }
+        let fixed = Polygon::new(self.fixed_radius, self.fixed_sides);
+        let rotating = Polygon::new(self.rotating_radius, self.rotating_sides);
+        fixed
+            .validate()
+            .and_then(|_| rotating.validate())
+            .map_err(|error| PresetError::Invalid(format!("can't roll these polygons: {error}")))?;
+        Ok((fixed, rotating))
+    }
+
+    fn state(&self) -> Result<State, PresetError> {
+        let (fixed, rotating) = self.polygons()?;
+        Ok(State::new(fixed, rotating))
     }
 }
 
-fn raw_window_event(_app: &App, model: &mut Model, event: &nannou::winit::event::WindowEvent) {
-    // Let egui handle things like keyboard and mouse input.
-    model.egui.handle_raw_event(event);
+impl PanelSettings for Settings {
+    fn fields(fields: &mut Fields<Self>) {
+        fields.slider(
+            "Radius of fixed polygon:",
+            |settings| &mut settings.fixed_radius,
+            40. ..=300.,
+        );
+        fields.slider(
+            "Radius of rotating polygon:",
+            |settings| &mut settings.rotating_radius,
+            40. ..=300.,
+        );
+        fields.slider(
+            "Sides of fixed polygon:",
+            |settings| &mut settings.fixed_sides,
+            3..=10,
+        );
+        fields.slider(
+            "Sides of rotating polygon:",
+            |settings| &mut settings.rotating_sides,
+            3..=10,
+        );
+        fields
+            .slider(
+                "Collisions to do:",
+                |settings| &mut settings.collisions_num,
+                -1..=MAX_COLLISIONS,
+            )
+            .tooltip("Jump straight to this many collisions, or -1 to animate the rolling");
+        fields
+            .slider("Speed:", |settings| &mut settings.speed, 1..=MAX_SPEED)
+            .tooltip("Steps taken each frame while animating");
+    }
 }
 
-pub fn update(app: &App, model: &mut Model, update: Update) {
-    model.egui.set_elapsed_time(update.since_start);
-    let ctx = model.egui.begin_frame();
+const STEPS_PER_SECOND: f32 = 60.;
+const MAX_COLLISIONS: i32 = 1000;
+const MAX_SPEED: u32 = 10;
 
-    let state = &mut model.state;
-    let settings = &mut model.settings;
-    let unapplied_settings = &mut model.unapplied_settings;
+pub struct Model {
+    state: State,
+    settings: SettingsPanel<Settings>,
+    /** The settings that state was last started from, which are always ones it can start from */
+    started_from: Settings,
+    /** Why the last settings couldn't be used, after which they were put back to started_from */
+    settings_error: Option<String>,
+    /** Rotates the polygon speed times every step */
+    clock: SimClock,
+    presets: Presets,
+    config: ConfigFile<Config>,
+}
 
-    let window = app.window_rect();
-    egui::Window::new("Settings")
-        .current_pos([window.x.len() / 10., window.y.len() / 4.])
-        .show(&ctx, |ui| {
-            create_ui(ui, state, settings, unapplied_settings);
+impl Model {
+    /**
+    Any change starts the rolling again from the top, unless it makes settings that can't be used,
+    in which case the settings go back to the last ones that could.
+    Edits from timelines and the control server only do if they change the polygons' shapes,
+    resizing them otherwise, as they come every frame and the polygons would never get to roll.
+    */
+    fn settings_changed(&mut self, change: Change) -> Result<(), PresetError> {
+        let settings = self.settings.settings().clone();
+        let started = settings.check_counts().and_then(|()| {
+            if change == Change::Edited && settings.same_shapes(&self.started_from) {
+                settings.polygons().map(|_| None)
+            } else {
+                settings.state().map(Some)
+            }
         });
-
-    if settings.collisions_num >= 0 {
-        while (state.collisions_num as i32) < settings.collisions_num {
-            rotate_things(state);
-        }
-    } else {
-        for _ in 0..settings.speed {
-            rotate_things(state);
+        match started {
+            Ok(state) => {
+                match state {
+                    Some(state) => self.state = state,
+                    None => resize(
+                        &mut self.state,
+                        settings.fixed_radius / self.started_from.fixed_radius,
+                        settings.rotating_radius / self.started_from.rotating_radius,
+                    ),
+                }
+                self.started_from = settings;
+                self.settings_error = None;
+                Ok(())
+            }
+            Err(error) => {
+                self.settings.set(self.started_from.clone());
+                self.settings_error = Some(format!("Settings weren't changed: {error}"));
+                Err(error)
+            }
         }
     }
 }
 
-fn create_ui(
-    ui: &mut Ui,
-    state: &mut State,
-    settings: &mut Settings,
-    unapplied_settings: &mut Settings,
-) {
-    ui.label("Radius of fixed polygon:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.fixed_radius,
-        (40.)..=(300.),
-    ));
-
-    ui.label("Radius of rotating polygon:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.rotating_radius,
-        (40.)..=(300.),
-    ));
-
-    ui.label("Sides of fixed polygon:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.fixed_sides,
-        3..=10,
-    ));
-
-    ui.label("Sides of rotating polygon:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.rotating_sides,
-        3..=10,
-    ));
+impl Sketch for Model {
+    const HAS_SETTINGS: bool = true;
 
-    ui.label("Collisions to do:");
-    ui.add(egui::Slider::new(
-        &mut unapplied_settings.collisions_num,
-        -1..=1000,
-    ));
+    fn init(ctx: &Context) -> Self {
+        let settings = SettingsPanel::<Settings>::new(Mode::Apply);
 
-    ui.label("Speed:");
-    ui.add(egui::Slider::new(&mut unapplied_settings.speed, 1..=10));
-
-    let apply = ui.button("Apply changes").clicked();
-    let reset = ui.button("Reset changes").clicked();
-
-    if apply {
-        *settings = unapplied_settings.clone();
-        *state = State::new(settings);
-    } else if reset {
-        *settings = Settings::new();
-        *unapplied_settings = settings.clone();
-        *state = State::new(&settings);
+        Model {
+            state: settings
+                .settings()
+                .state()
+                .expect("Default settings should make valid polygons"),
+            started_from: settings.settings().clone(),
+            settings_error: None,
+            settings,
+            clock: SimClock::new(STEPS_PER_SECOND),
+            presets: Presets::new(ctx, "epicyclogons"),
+            config: ConfigFile::load(ctx, "epicyclogons"),
+        }
     }
-}
 
-fn rotate_things(state: &mut State) {
-    let mut angle = angle_between_points(
-        state.next_point_rotating.0,
-        state.rotating_point.0,
-        state.next_point_fixed.0,
-    );
+    fn update(&mut self, _ctx: &Context, dt: f32) {
+        self.config.reload_if_changed();
+        let rotating_angle = self.config.rotating_angle.to_radians();
+        let settings = self.settings.settings();
+        let state = &mut self.state;
+        if settings.collisions_num >= 0 {
+            roll_to_collision(state, settings.collisions_num as u32, rotating_angle);
+        } else {
+            for _ in 0..self.clock.advance(dt) * settings.speed {
+                rotate_things(state, rotating_angle);
+            }
+        }
+    }
 
-    if angle > ROTATING_ANGLE {
-        angle = 2. * PI - ROTATING_ANGLE;
-        state
-            .rotating
-            .rotate_around_point(state.rotating_point.0, angle);
-        rotate_point(
-            &mut state.tracing_point,
-            state.rotating_point.0,
-            angle.sin(),
-            angle.cos(),
-        );
+    fn draw(&self, _ctx: &Context, canvas: &mut Canvas) {
+        let state = &self.state;
+        canvas.background(WHITE);
 
-        state.next_point_rotating.0 = state.rotating.points[state.next_point_rotating.1];
-        state.traced_path.push(state.tracing_point);
+        canvas.polygon(state.fixed.points.clone()).color(LIGHTBLUE);
+        canvas
+            .polygon(state.rotating.points.clone())
+            .color(LIGHTGREEN);
+        if !state.traced_path.is_empty() {
+            canvas.polyline(state.traced_path.clone()).color(RED);
+        }
+    }
 
-        return;
+    fn settings_ui(&mut self, _ctx: &Context, ui: &mut Ui) -> Option<Change> {
+        let mut change = self.settings.ui(ui);
+        if let Some(settings) = self.presets.ui(ui, self.settings.settings()) {
+            self.settings.set(settings);
+            change = Some(Change::Loaded);
+        }
+        if let Some(change) = change {
+            // The error is shown by error() instead
+            let _ = self.settings_changed(change);
+        }
+        change
     }
 
-    state.collisions_num = state.collisions_num + 1;
+    fn settings(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self.settings.settings()).ok()
+    }
 
-    if state.rotating.distance_to_point(state.next_point_fixed.0)
-        < state.fixed.distance_to_point(state.next_point_rotating.0)
-    {
-        state.rotating_point = state.next_point_fixed.clone();
-        (state.next_point_fixed).1 = if (state.next_point_fixed).1 == state.fixed.points.len() - 1 {
-            0
-        } else {
-            (state.next_point_fixed).1 + 1
-        };
-        (state.next_point_fixed).0 = state.fixed.points[(state.next_point_fixed).1];
-        state.next_point_rotating.0 = state.rotating.points[state.next_point_rotating.1];
-    } else {
-        state.rotating_point = state.next_point_rotating.clone();
-        (state.next_point_rotating).1 = if (state.next_point_rotating).1 == 0 {
-            state.rotating.points.len() - 1
-        } else {
-            (state.next_point_rotating).1 - 1
-        };
-        (state.next_point_rotating).0 = state.rotating.points[(state.next_point_rotating).1];
-        state.next_point_fixed.0 = state.fixed.points[state.next_point_fixed.1];
+    fn apply_settings(
+        &mut self,
+        _ctx: &Context,
+        change: Change,
+        values: serde_json::Value,
+    ) -> Result<(), PresetError> {
+        self.settings
+            .set(serde_json::from_value(values).map_err(PresetError::Json)?);
+        self.settings_changed(change)
     }
-}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let state = &model.state;
-    let draw = app.draw();
-    draw.background().color(WHITE);
+    fn counters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("traced points", self.state.traced_path.len() as f64),
+            ("collisions", self.state.collisions_num as f64),
+        ]
+    }
 
-    draw.polygon()
-        .points(state.fixed.points.clone())
-        .color(LIGHTBLUE);
-    draw.polygon()
-        .points(state.rotating.points.clone())
-        .color(LIGHTGREEN);
-    if !state.traced_path.is_empty() {
-        draw.polyline().points(state.traced_path.clone()).color(RED);
+    fn clock(&mut self) -> Option<&mut SimClock> {
+        Some(&mut self.clock)
     }
 
-    draw.to_frame(app, &frame).unwrap();
-    model.egui.draw_to_frame(&frame).unwrap();
+    fn error(&self) -> Option<&str> {
+        self.config.error().or(self.settings_error.as_deref())
+    }
 }
@@ -1,182 +1,191 @@
-use nannou::prelude::*;
-
-const FPS: u64 = 5;
-const CELL_WIDTH: f32 = 30.;
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum GameState {
-    Paused,
-    NextFrame,
-    Running,
+use crate::{
+    bindings::{Binding, Bindings},
+    canvas::Canvas,
+    clock::SimClock,
+    config::ConfigFile,
+    simulations::game_of_life::Life,
+    sketch::{Context, Input, Sketch},
+};
+use nannou::{prelude::*, rand::rngs::StdRng};
+use serde::Deserialize;
+
+/** Constants that are read from assets/config/game_of_life.toml, and reloaded when it changes */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /** Generations per second while running */
+    fps: f32,
+    cell_width: f32,
 }
 
-impl GameState {
-    /** Toggle the game state between Paused and Running. */
-    fn toggle(&mut self) -> Self {
-        match self {
-            GameState::Paused => GameState::Running,
-            GameState::Running => GameState::Paused,
-            GameState::NextFrame => GameState::Paused,
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            fps: 5.,
+            cell_width: 30.,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Action {
+    ToggleRunning,
+    Randomise,
+    Clear,
+    Step,
+    ToggleCell,
+}
+
+const BINDINGS: &[Binding<Action>] = &[
+    Binding::key(
+        Action::ToggleRunning,
+        "toggle_running",
+        Key::Space,
+        "Start or pause the game",
+    ),
+    Binding::key(
+        Action::Randomise,
+        "randomise",
+        Key::R,
+        "Fill the grid with random cells",
+    ),
+    Binding::key(Action::Clear, "clear", Key::Back, "Clear the grid"),
+    Binding::key(Action::Step, "step", Key::Right, "Advance one generation"),
+    Binding::mouse(
+        Action::ToggleCell,
+        "toggle_cell",
+        MouseButton::Left,
+        "Toggle the cell under the mouse while paused",
+    ),
+];
+
+/** Rows and columns in the grid */
+const SIZE: usize = 20;
+
 pub struct Model {
-    // Elements of outer Vec are the rows, bool values are the cells
-    cells: Vec<Vec<bool>>,
-    state: GameState,
+    life: Life,
+    // Steps once per generation, at the rate set in the config
+    clock: SimClock,
+    rng: StdRng,
+    config: ConfigFile<Config>,
+    bindings: Bindings<Action>,
 }
 
 impl Model {
-    pub fn new(app: &App) -> Self {
-        app.new_window()
-            .mouse_pressed(mouse_pressed)
-            .key_pressed(key_pressed)
-            .view(view)
-            .build()
-            .unwrap();
-
-        Self::random_state()
+    fn clear_state(&mut self) {
+        self.life = Life::empty(SIZE, SIZE);
+        self.clock.set_paused(true);
+        self.clock.reset();
     }
 
-    fn clear_state() -> Self {
-        Model {
-            cells: vec![vec![false; 20]; 20],
-            state: GameState::Paused,
-        }
+    fn random_state(&mut self) {
+        self.clear_state();
+        self.life = Life::random(SIZE, SIZE, &mut self.rng);
     }
+}
 
-    fn random_state() -> Self {
-        let mut model = Self::clear_state();
-
-        for row in &mut model.cells {
-            for cell in row {
-                *cell = random_f32() > 0.5;
-            }
-        }
+impl Sketch for Model {
+    fn init(ctx: &Context) -> Self {
+        let config = ConfigFile::<Config>::load(ctx, "game_of_life");
+        let mut model = Model {
+            life: Life::empty(SIZE, SIZE),
+            clock: SimClock::new(config.fps),
+            rng: ctx.rng(),
+            config,
+            bindings: Bindings::new(ctx, "game_of_life", BINDINGS),
+        };
+        model.random_state();
         model
     }
-}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let window_width = app.window_rect().w();
-    let window_height = app.window_rect().h();
-    let draw = app.draw();
-    draw.background().color(WHITE);
-
-    for (i, row) in model.cells.iter().enumerate() {
-        for (j, cell) in row.iter().enumerate() {
-            let x = CELL_WIDTH * (i as f32 + 0.5) - window_width / 2.;
-            let y = CELL_WIDTH * (j as f32 + 0.5) - window_height / 2.;
-
-            if *cell {
-                draw.rect()
-                    .x_y(x, y)
-                    .w_h(CELL_WIDTH, CELL_WIDTH)
-                    .color(BLACK)
-                    .stroke(GREY)
-                    .stroke_weight(1.);
-            } else {
-                draw.rect()
-                    .x_y(x, y)
-                    .w_h(CELL_WIDTH, CELL_WIDTH)
-                    .stroke(GREY)
-                    .stroke_weight(1.);
-            }
+    fn update(&mut self, _ctx: &Context, dt: f32) {
+        if self.config.reload_if_changed() {
+            self.clock.set_steps_per_second(self.config.fps);
         }
-    }
-
-    draw.to_frame(app, &frame).unwrap();
-}
+        self.bindings.reload_if_changed();
 
-/** Update the cells according to the rules of Conway's Game of Life. */
-pub fn update(app: &App, model: &mut Model, _update: Update) {
-    if model.state == GameState::Paused || app.elapsed_frames() % (60 / FPS) != 0 {
-        return;
+        for _ in 0..self.clock.advance(dt) {
+            self.life.step();
+        }
     }
-    let cells = model.cells.clone();
-    let rows = cells.len();
-    let columns = cells[0].len();
-    let mut new_cells = vec![vec![false; columns]; rows];
-
-    // Iterate through the cells.
-    for i in 0..rows as usize {
-        for j in 0..columns as usize {
-            // Count the number of live neighbors.
-            let mut count = 0;
-            for x in -1..=1 {
-                for y in -1..=1 {
-                    if x == 0 && y == 0 {
-                        continue;
-                    }
-
-                    let i = i as i32 + x;
-                    let j = j as i32 + y;
-
-                    if i < 0 || i >= rows as i32 {
-                        continue;
-                    }
-                    if j < 0 || j >= columns as i32 {
-                        continue;
-                    }
-
-                    if cells[i as usize][j as usize] {
-                        count += 1;
-                    }
+
+    fn draw(&self, ctx: &Context, canvas: &mut Canvas) {
+        let window_width = ctx.window_rect().w();
+        let window_height = ctx.window_rect().h();
+        let cell_width = self.config.cell_width;
+        canvas.background(WHITE);
+
+        for (i, row) in self.life.cells.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                let x = cell_width * (i as f32 + 0.5) - window_width / 2.;
+                let y = cell_width * (j as f32 + 0.5) - window_height / 2.;
+
+                let rect = Rect::from_x_y_w_h(x, y, cell_width, cell_width);
+                if *cell {
+                    canvas
+                        .rect(rect)
+                        .color(BLACK)
+                        .stroke(GREY)
+                        .stroke_weight(1.);
+                } else {
+                    canvas.rect(rect).stroke(GREY).stroke_weight(1.);
                 }
             }
+        }
+    }
 
-            if cells[i][j] {
-                new_cells[i][j] = count == 2 || count == 3;
-            } else {
-                new_cells[i][j] = count == 3;
-            }
+    fn input(&mut self, ctx: &Context, input: Input) {
+        match self.bindings.pressed(&input) {
+            Some(Action::ToggleRunning) => self.clock.toggle_paused(),
+            Some(Action::Randomise) => self.random_state(),
+            Some(Action::Clear) => self.clear_state(),
+            Some(Action::Step) => self.clock.step_once(),
+            Some(Action::ToggleCell) => toggle_cell(ctx, self, ctx.mouse()),
+            None => {}
         }
     }
 
-    model.cells = new_cells;
-    if model.state == GameState::NextFrame {
-        model.state = GameState::Paused;
+    fn counters(&self) -> Vec<(&'static str, f64)> {
+        let live_cells = self
+            .life
+            .cells
+            .iter()
+            .flatten()
+            .filter(|cell| **cell)
+            .count();
+        vec![("live cells", live_cells as f64)]
     }
-}
 
-/** When user clicks left mouse button, find the cell that was clicked and toggle it. */
-fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
-    match button {
-        MouseButton::Left => {}
-        _ => return,
+    fn help(&self) -> Vec<(String, &'static str)> {
+        self.bindings.help()
     }
-    if model.state == GameState::Running {
-        return;
+
+    fn clock(&mut self) -> Option<&mut SimClock> {
+        Some(&mut self.clock)
     }
 
-    let window = app.window_rect();
-    let mouse = app.mouse.position();
+    fn autoplay(&mut self, _ctx: &Context) {
+        self.clock.set_paused(false);
+    }
 
-    let i = ((mouse.x + window.w() / 2.) / CELL_WIDTH) as i32;
-    let j = ((mouse.y + window.h() / 2.) / CELL_WIDTH) as i32;
+    fn error(&self) -> Option<&str> {
+        self.config.error().or(self.bindings.error())
+    }
+}
 
-    let i_is_invalid = i < 0 || i >= model.cells.len() as i32;
-    let j_is_invalid = j < 0 || j >= model.cells[0].len() as i32;
-    if i_is_invalid || j_is_invalid {
+/** Find the cell under the mouse and toggle it. */
+fn toggle_cell(ctx: &Context, model: &mut Model, mouse: Point2) {
+    if !model.clock.paused() {
         return;
     }
 
-    model.cells[i as usize][j as usize] ^= true;
-}
+    let window = ctx.window_rect();
 
-/** When user presses the space bar, toggle the animation. */
-fn key_pressed(_app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::Space => model.state = model.state.toggle(),
-        Key::R => {
-            *model = Model::random_state();
-        }
-        Key::Back => {
-            *model = Model::clear_state();
-        }
-        Key::Right => model.state = GameState::NextFrame,
-        _ => {}
+    let i = ((mouse.x + window.w() / 2.) / model.config.cell_width) as i32;
+    let j = ((mouse.y + window.h() / 2.) / model.config.cell_width) as i32;
+
+    if i < 0 || j < 0 {
+        return;
     }
+    model.life.toggle(i as usize, j as usize);
 }
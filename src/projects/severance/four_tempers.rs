@@ -1,206 +1,278 @@
 use super::colors;
+use crate::{
+    bindings::{Binding, Bindings},
+    canvas::Canvas,
+    clock::SimClock,
+    config::ConfigFile,
+    presets::PresetError,
+    settings_panel::Change,
+    simulations::four_tempers::{self, Temper, FIXED},
+    sketch::{Context, Input, Sketch, WindowOptions},
+};
 use itertools::Itertools;
-use nannou::{geom::bounding_rect, prelude::*};
-
-const G: f32 = 9.81;
-const DEFAULT_TEMPER_MASS: f32 = 5.;
-
-#[derive(Clone, Debug, PartialEq)]
-struct Temper {
-    position: Point2,
-    velocity: Vec2,
-    acceleration: Vec2,
-    mass: f32,
-    color: Srgb<u8>,
+use nannou::{geom::bounding_rect, prelude::*, rand::rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+/** Constants that are read from assets/config/four_tempers.toml, and reloaded when it changes */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /** Gravitational constant */
+    g: f32,
+    /** Mass of the four moving tempers, when none of 1 to 4 are held */
+    default_temper_mass: f32,
 }
 
-impl Default for Temper {
+impl Default for Config {
     fn default() -> Self {
-        Temper {
-            position: pt2(0., 0.),
-            velocity: vec2(0., 0.),
-            acceleration: vec2(0., 0.),
-            mass: DEFAULT_TEMPER_MASS,
-            color: colors::SILVER,
+        Config {
+            g: 9.81,
+            default_temper_mass: 5.,
         }
     }
 }
 
-impl Temper {
-    fn new(color: Srgb<u8>, window: Rect) -> Self {
-        Temper {
-            position: pt2(
-                random_range(window.x.start, window.x.end),
-                random_range(window.y.start, window.y.end),
-            ),
-            color,
-            ..Default::default()
-        }
-    }
+/** What can be changed from outside the sketch, like through the control server */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    /** Masses of the four moving tempers, in the same order as COLORS */
+    masses: [f32; FIXED],
+}
 
-    fn set_mass(&mut self, mass: f32) -> &mut Self {
-        self.mass = mass;
-        return self;
-    }
+/** Colours of the tempers, in the same order as they are made by new_tempers */
+const COLORS: [Srgb<u8>; 5] = [
+    colors::RED,
+    colors::LIGHTBLUE,
+    colors::GREEN,
+    colors::YELLOW,
+    colors::SILVER,
+];
 
-    fn draw(&self, draw: &Draw) {
-        draw.ellipse()
-            .xy(self.position)
-            .radius(self.mass)
-            .color(self.color);
-    }
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Action {
+    ShowBoundingBox,
+    ShowLinesToFixed,
+    ShowLinesToOtherTempers,
+    Restart,
+    /** Index of the temper that gets heavier while the key is held */
+    Heavier(usize),
+}
 
-    fn apply_force(&mut self, force: Vec2) {
-        self.acceleration += force / self.mass;
-    }
+const BINDINGS: &[Binding<Action>] = &[
+    Binding::key(
+        Action::ShowBoundingBox,
+        "show_bounding_box",
+        Key::B,
+        "Hold to show the box around the tempers",
+    ),
+    Binding::key(
+        Action::ShowLinesToFixed,
+        "show_lines_to_fixed",
+        Key::F,
+        "Hold to show lines to the fixed temper",
+    ),
+    Binding::key(
+        Action::ShowLinesToOtherTempers,
+        "show_lines_to_other_tempers",
+        Key::O,
+        "Hold to show lines between the tempers",
+    ),
+    Binding::key(
+        Action::Restart,
+        "restart",
+        Key::R,
+        "Scatter the tempers again",
+    ),
+    Binding::key(
+        Action::Heavier(0),
+        "heavier_1",
+        Key::Key1,
+        "Hold to make the red temper heavier",
+    ),
+    Binding::key(
+        Action::Heavier(1),
+        "heavier_2",
+        Key::Key2,
+        "Hold to make the blue temper heavier",
+    ),
+    Binding::key(
+        Action::Heavier(2),
+        "heavier_3",
+        Key::Key3,
+        "Hold to make the green temper heavier",
+    ),
+    Binding::key(
+        Action::Heavier(3),
+        "heavier_4",
+        Key::Key4,
+        "Hold to make the yellow temper heavier",
+    ),
+];
 
-    fn update(&mut self) {
-        let new_velocity = self.velocity + self.acceleration;
-        let speed = new_velocity.length();
-        if speed > 20. {
-            self.velocity = new_velocity.normalize() * 15.;
-        } else {
-            self.velocity = new_velocity;
-        }
-        self.position += self.velocity;
-        self.acceleration *= 0.;
-    }
-
-    fn attract(&self, other_temper: &Temper) -> Vec2 {
-        // Calculate direction of force
-        let force = self.position - other_temper.position;
-        // Distance between objects
-        // Limiting the distance to eliminate "extreme" results for very close or very far objects
-        let distance = force.length().min(25.);
-
-        // Calculate gravitional force magnitude
-        let strength = (G * self.mass * other_temper.mass) / distance.pow(2);
-        // Get force vector --> magnitude * direction
-        force.normalize() * strength
-    }
-}
+/** Tempers are moved by their velocity this many times a second */
+const STEPS_PER_SECOND: f32 = 60.;
 
 pub struct Model {
     tempers: [Temper; 5],
     show_bounding_box: bool,
     show_lines_to_fixed: bool,
     show_lines_to_other_tempers: bool,
+    clock: SimClock,
+    rng: StdRng,
+    config: ConfigFile<Config>,
+    bindings: Bindings<Action>,
 }
 
-impl Model {
-    pub fn new(app: &App) -> Self {
-        app.new_window()
-            .size_pixels(640, 800)
-            .view(view)
-            .key_pressed(key_pressed)
-            .key_released(key_released)
-            .build()
-            .unwrap();
-        let window = app.window_rect();
+impl Sketch for Model {
+    fn window() -> WindowOptions {
+        WindowOptions {
+            size: Some([640, 800]),
+            ..Default::default()
+        }
+    }
 
+    fn init(ctx: &Context) -> Self {
+        let mut rng = ctx.rng();
+        let config = ConfigFile::<Config>::load(ctx, "four_tempers");
         Model {
-            tempers: Model::new_tempers(window),
+            tempers: four_tempers::new_tempers(
+                ctx.window_rect(),
+                config.default_temper_mass,
+                &mut rng,
+            ),
             show_bounding_box: false,
             show_lines_to_fixed: false,
             show_lines_to_other_tempers: false,
+            clock: SimClock::new(STEPS_PER_SECOND),
+            rng,
+            config,
+            bindings: Bindings::new(ctx, "four_tempers", BINDINGS),
         }
     }
 
-    fn new_tempers(window: Rect) -> [Temper; 5] {
-        [
-            Temper::new(colors::RED, window),
-            Temper::new(colors::LIGHTBLUE, window),
-            Temper::new(colors::GREEN, window),
-            Temper::new(colors::YELLOW, window),
-            Temper::default().set_mass(20.).clone(),
-        ]
+    fn update(&mut self, _ctx: &Context, dt: f32) {
+        if self.config.reload_if_changed() {
+            for temper in &mut self.tempers[..FIXED] {
+                temper.mass = self.config.default_temper_mass;
+            }
+        }
+        self.bindings.reload_if_changed();
+        for _ in 0..self.clock.advance(dt) {
+            four_tempers::step(&mut self.tempers, self.config.g);
+        }
     }
-}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-    draw.background().color(colors::DARKBLUE);
-
-    if model.show_bounding_box {
-        let bounding_box =
-            bounding_rect(model.tempers.clone().iter().map(|temper| temper.position)).unwrap();
-        draw.rect()
-            .xy(bounding_box.xy())
-            .wh(bounding_box.wh())
-            .no_fill()
-            .stroke_color(colors::SILVER)
-            .stroke_weight(5.);
-    }
-    if model.show_lines_to_fixed {
-        for temper in &model.tempers {
-            draw.line()
-                .start(temper.position)
-                .end(model.tempers[4].position)
-                .color(temper.color)
+    fn draw(&self, _ctx: &Context, canvas: &mut Canvas) {
+        canvas.background(colors::DARKBLUE);
+
+        if self.show_bounding_box {
+            let bounding_box =
+                bounding_rect(self.tempers.clone().iter().map(|temper| temper.position)).unwrap();
+            canvas
+                .rect(bounding_box)
+                .no_fill()
+                .stroke(colors::SILVER)
                 .stroke_weight(5.);
         }
-    }
-    if model.show_lines_to_other_tempers {
-        let (_, tempers) = model
-            .tempers
-            .split_last()
-            .expect("Tempers should not be empty");
-
-        for combination in tempers.iter().combinations(2) {
-            draw.line()
-                .start(combination[0].position)
-                .end(combination[1].position)
-                .color(colors::SILVER)
-                .stroke_weight(5.);
+        if self.show_lines_to_fixed {
+            for (temper, color) in self.tempers.iter().zip(COLORS) {
+                canvas
+                    .line(temper.position, self.tempers[FIXED].position)
+                    .color(color)
+                    .stroke_weight(5.);
+            }
+        }
+        if self.show_lines_to_other_tempers {
+            let (_, tempers) = self
+                .tempers
+                .split_last()
+                .expect("Tempers should not be empty");
+
+            for combination in tempers.iter().combinations(2) {
+                canvas
+                    .line(combination[0].position, combination[1].position)
+                    .color(colors::SILVER)
+                    .stroke_weight(5.);
+            }
+        }
+
+        for (temper, color) in self.tempers.iter().zip(COLORS) {
+            canvas.ellipse(temper.position, temper.mass).color(color);
         }
     }
 
-    for temper in &model.tempers {
-        temper.draw(&draw);
+    fn input(&mut self, ctx: &Context, input: Input) {
+        if let Some(action) = self.bindings.pressed(&input) {
+            action_started(ctx, self, action);
+        }
+        if let Some(action) = self.bindings.released(&input) {
+            action_ended(self, action);
+        }
     }
 
-    draw.to_frame(app, &frame).unwrap();
-}
+    fn settings(&self) -> Option<serde_json::Value> {
+        let settings = Settings {
+            masses: std::array::from_fn(|i| self.tempers[i].mass),
+        };
+        serde_json::to_value(settings).ok()
+    }
 
-pub fn update(_app: &App, model: &mut Model, _update: Update) {
-    let tempers = model.tempers.clone();
-    for (i, temper) in model.tempers.iter_mut().enumerate() {
-        if i == 4 {
-            continue;
+    fn apply_settings(
+        &mut self,
+        _ctx: &Context,
+        _change: Change,
+        values: serde_json::Value,
+    ) -> Result<(), PresetError> {
+        let settings: Settings = serde_json::from_value(values).map_err(PresetError::Json)?;
+        // The forces are divided by the masses, so any other mass would make every position NaN
+        if let Some(mass) = settings
+            .masses
+            .iter()
+            .find(|mass| !(mass.is_finite() && **mass > 0.))
+        {
+            return Err(PresetError::Invalid(format!(
+                "masses must be more than 0, not {mass}"
+            )));
         }
-        for (j, other_temper) in tempers.iter().enumerate() {
-            if i == j {
-                continue;
-            }
-            temper.apply_force(other_temper.attract(temper));
+        for (temper, mass) in self.tempers.iter_mut().zip(settings.masses) {
+            temper.mass = mass;
         }
-        temper.update();
+        Ok(())
+    }
+
+    fn clock(&mut self) -> Option<&mut SimClock> {
+        Some(&mut self.clock)
+    }
+
+    fn help(&self) -> Vec<(String, &'static str)> {
+        self.bindings.help()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.config.error().or(self.bindings.error())
     }
 }
 
-fn key_pressed(app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::B => model.show_bounding_box = true,
-        Key::F => model.show_lines_to_fixed = true,
-        Key::O => model.show_lines_to_other_tempers = true,
-        Key::R => model.tempers = Model::new_tempers(app.window_rect()),
-        Key::Key1 => model.tempers[0].mass += 0.5,
-        Key::Key2 => model.tempers[1].mass += 0.5,
-        Key::Key3 => model.tempers[2].mass += 0.5,
-        Key::Key4 => model.tempers[3].mass += 0.5,
-        _ => return,
+fn action_started(ctx: &Context, model: &mut Model, action: Action) {
+    match action {
+        Action::ShowBoundingBox => model.show_bounding_box = true,
+        Action::ShowLinesToFixed => model.show_lines_to_fixed = true,
+        Action::ShowLinesToOtherTempers => model.show_lines_to_other_tempers = true,
+        Action::Restart => {
+            let mass = model.config.default_temper_mass;
+            model.tempers = four_tempers::new_tempers(ctx.window_rect(), mass, &mut model.rng);
+        }
+        Action::Heavier(index) => model.tempers[index].mass += 0.5,
     }
 }
 
-fn key_released(_app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::B => model.show_bounding_box = false,
-        Key::F => model.show_lines_to_fixed = false,
-        Key::O => model.show_lines_to_other_tempers = false,
-        Key::Key1 => model.tempers[0].mass = DEFAULT_TEMPER_MASS,
-        Key::Key2 => model.tempers[1].mass = DEFAULT_TEMPER_MASS,
-        Key::Key3 => model.tempers[2].mass = DEFAULT_TEMPER_MASS,
-        Key::Key4 => model.tempers[3].mass = DEFAULT_TEMPER_MASS,
-        _ => return,
+fn action_ended(model: &mut Model, action: Action) {
+    match action {
+        Action::ShowBoundingBox => model.show_bounding_box = false,
+        Action::ShowLinesToFixed => model.show_lines_to_fixed = false,
+        Action::ShowLinesToOtherTempers => model.show_lines_to_other_tempers = false,
+        Action::Heavier(index) => model.tempers[index].mass = model.config.default_temper_mass,
+        Action::Restart => (),
     }
 }
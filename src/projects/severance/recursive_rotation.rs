@@ -1,181 +1,136 @@
-use crate::geometry::PointU32;
+use crate::{
+    bindings::{Binding, Bindings},
+    canvas::Canvas,
+    presets::PresetError,
+    settings_panel::Change,
+    simulations::recursive_rotation::Rotation,
+    sketch::{Context, Input, Sketch, WindowOptions},
+};
 use nannou::{
-    image::{
-        io::Reader,
-        {DynamicImage, GenericImage, GenericImageView, RgbaImage},
-    },
+    image::{io::Reader, DynamicImage},
     prelude::*,
-    wgpu::Texture,
 };
+use serde::{Deserialize, Serialize};
 
 // assuming the image is square and its side length is a power of 2
 const IMAGE_PATH: &str = "ms_casey.jpg";
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Tile {
-    row: u32,
-    column: u32,
+const IMAGE_WIDTH: u32 = 512;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Action {
+    RotateDeeper,
+    RotateBack,
+    ResetLayers,
+    ReloadImage,
 }
 
-impl Tile {
-    fn top_left_coords(&self, tile_width: u32) -> PointU32 {
-        PointU32::new(self.row, self.column) * tile_width
-    }
-
-    /**
-    Find the tile that should be translated into the position of the tile given through the parameters
-    Works for both clockwise and anticlockwise translation
-    */
-    fn source_tile(&self, decreased_recursion_layers: bool) -> Tile {
-        match (
-            self.row % 2 == 0,
-            self.column % 2 == 0,
-            decreased_recursion_layers,
-        ) {
-            // top left increasing or top right decreasing
-            (true, true, true) | (true, false, false) => Tile {
-                row: self.row + 1,
-                column: self.column,
-            },
-            // top right increasing or bottom right decreasing
-            (true, false, true) | (false, false, false) => Tile {
-                row: self.row,
-                column: self.column - 1,
-            },
-            // bottom left increasing or top left decreasing
-            (false, true, true) | (true, true, false) => Tile {
-                row: self.row,
-                column: self.column + 1,
-            },
-            // bottom right or bottom left decreasing
-            (false, false, true) | (false, true, false) => Tile {
-                row: self.row - 1,
-                column: self.column,
-            },
-        }
-    }
+const BINDINGS: &[Binding<Action>] = &[
+    Binding::key(
+        Action::RotateDeeper,
+        "rotate_deeper",
+        Key::Right,
+        "Rotate the tiles of the next layer down",
+    ),
+    Binding::key(
+        Action::RotateBack,
+        "rotate_back",
+        Key::Left,
+        "Undo the rotation of the last layer",
+    ),
+    Binding::key(
+        Action::ResetLayers,
+        "reset_layers",
+        Key::Space,
+        "Go back to the first layer without changing the image",
+    ),
+    Binding::key(
+        Action::ReloadImage,
+        "reload_image",
+        Key::R,
+        "Start again from the original image",
+    ),
+];
+
+/** What can be changed from outside the sketch, like through the control server */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    /** The layer the image has been rotated down to, rotating it deeper or back to get there */
+    recursion_layers: u32,
 }
 
 pub struct Model {
-    image: DynamicImage,
-    recursion_layers: u32,
+    rotation: Rotation,
+    bindings: Bindings<Action>,
 }
 
-impl Model {
-    pub fn new(app: &App) -> Self {
-        let image = make_image(app);
-
-        if image.width() != image.height() {
-            panic!(
-                "Image width is {0} pixels and height is {1} pixels, but image should be square",
-                image.width(),
-                image.height()
-            );
+impl Sketch for Model {
+    fn window() -> WindowOptions {
+        WindowOptions {
+            size: Some([IMAGE_WIDTH, IMAGE_WIDTH]),
+            ..Default::default()
         }
-        if !image.width().is_power_of_two() {
-            panic!(
-                "Image width is {0}, which is not a power of 2",
-                image.width()
-            )
-        }
-
-        app.new_window()
-            .size(image.width(), image.width())
-            .view(view)
-            .key_pressed(key_pressed)
-            .build()
-            .unwrap();
+    }
 
+    fn init(ctx: &Context) -> Self {
         Model {
-            image,
-            recursion_layers: 1,
+            rotation: Rotation::new(make_image(ctx)),
+            bindings: Bindings::new(ctx, "recursive_rotation", BINDINGS),
         }
     }
-}
-
-fn make_image(app: &App) -> DynamicImage {
-    let assets = app.assets_path().unwrap();
-    let img_path = assets.join(IMAGE_PATH);
-    Reader::open(img_path).unwrap().decode().unwrap()
-}
 
-fn find_number_of_rows(recursion_layers: u32) -> u32 {
-    2u32.pow(recursion_layers.max(1) - 1)
-}
+    fn draw(&self, ctx: &Context, canvas: &mut Canvas) {
+        canvas.image(self.rotation.image.clone(), ctx.window_rect());
+    }
 
-fn find_number_of_layers(rows: u32) -> u32 {
-    if rows == 0 {
-        1
-    } else {
-        rows.next_power_of_two().trailing_zeros() + 1
+    fn update(&mut self, _ctx: &Context, _dt: f32) {
+        self.bindings.reload_if_changed();
     }
-}
 
-fn process_tiles(
-    old_image: &DynamicImage,
-    recursion_layers: u32,
-    decreased_recursion_layers: bool,
-) -> DynamicImage {
-    let rows = find_number_of_rows(recursion_layers);
-    let tile_width = old_image.width() / rows;
-
-    let mut new_image =
-        DynamicImage::ImageRgba8(RgbaImage::new(old_image.width(), old_image.width()));
-
-    for row in 0..rows {
-        for column in 0..rows {
-            let destination_tile = Tile { row, column };
-            let source_tile = destination_tile.source_tile(decreased_recursion_layers);
-
-            let destination_tile_position = destination_tile.top_left_coords(tile_width);
-            let source_tile_position = source_tile.top_left_coords(tile_width);
-
-            for x in 0..tile_width {
-                for y in 0..tile_width {
-                    let pixel =
-                        old_image.get_pixel(source_tile_position.x + x, source_tile_position.y + y);
-                    new_image.put_pixel(
-                        destination_tile_position.x + x,
-                        destination_tile_position.y + y,
-                        pixel,
-                    );
-                }
-            }
+    fn input(&mut self, ctx: &Context, input: Input) {
+        if let Some(action) = self.bindings.pressed(&input) {
+            apply_action(ctx, self, action);
         }
     }
 
-    new_image
+    fn settings(&self) -> Option<serde_json::Value> {
+        let settings = Settings {
+            recursion_layers: self.rotation.recursion_layers,
+        };
+        serde_json::to_value(settings).ok()
+    }
+
+    fn apply_settings(
+        &mut self,
+        _ctx: &Context,
+        _change: Change,
+        values: serde_json::Value,
+    ) -> Result<(), PresetError> {
+        let settings: Settings = serde_json::from_value(values).map_err(PresetError::Json)?;
+        self.rotation.rotate_to(settings.recursion_layers);
+        Ok(())
+    }
+
+    fn help(&self) -> Vec<(String, &'static str)> {
+        self.bindings.help()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.bindings.error()
+    }
 }
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-    draw.texture(&Texture::from_image(app, &model.image));
-    draw.to_frame(app, &frame).unwrap();
+fn make_image(ctx: &Context) -> DynamicImage {
+    let assets = ctx.assets_path();
+    let img_path = assets.join(IMAGE_PATH);
+    Reader::open(img_path).unwrap().decode().unwrap()
 }
 
-fn key_pressed(app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::Right => {
-            model.recursion_layers += 1;
-            if model.image.width() / find_number_of_rows(model.recursion_layers) <= 1 {
-                model.recursion_layers = 2;
-            }
-            model.image = process_tiles(&model.image, model.recursion_layers, false);
-        }
-        Key::Left => {
-            if model.recursion_layers == 1 {
-                model.recursion_layers = find_number_of_layers(model.image.width()) - 1;
-            }
-            model.image = process_tiles(&model.image, model.recursion_layers, true);
-            model.recursion_layers -= 1;
-        }
-        Key::Space => {
-            model.recursion_layers = 2;
-        }
-        Key::R => {
-            model.image = make_image(app);
-            model.recursion_layers = 1;
-        }
-        _ => return,
+fn apply_action(ctx: &Context, model: &mut Model, action: Action) {
+    match action {
+        Action::RotateDeeper => model.rotation.rotate_deeper(),
+        Action::RotateBack => model.rotation.rotate_back(),
+        Action::ResetLayers => model.rotation.reset_layers(),
+        Action::ReloadImage => model.rotation = Rotation::new(make_image(ctx)),
     }
 }
@@ -1,5 +1,8 @@
-use crate::geometry::{Direction, Polygon, NO_VERTICES_ERROR};
-use geom::bounding_rect;
+use crate::{
+    canvas::Canvas,
+    geometry::{Direction, Polygon, NO_VERTICES_ERROR},
+    sketch::{Context, Sketch, WindowOptions},
+};
 use nannou::prelude::*;
 
 const ORIGINAL_WIDTH: f32 = 100.;
@@ -8,57 +11,60 @@ pub struct Model {
     polygon: Polygon,
 }
 
-pub fn model(app: &App) -> Model {
-    app.new_window().fullscreen().view(view).build().unwrap();
-
-    Model {
-        polygon: Polygon::new(ORIGINAL_WIDTH, 4),
+impl Sketch for Model {
+    fn window() -> WindowOptions {
+        WindowOptions {
+            fullscreen: true,
+            ..Default::default()
+        }
     }
-}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let window = app.window_rect();
-    let draw = app.draw();
-    draw.background().color(WHITE);
+    fn init(_ctx: &Context) -> Self {
+        Model {
+            polygon: Polygon::new(ORIGINAL_WIDTH, 4),
+        }
+    }
 
-    let polygon = model.polygon.clone();
-    let bounding_box = bounding_rect(polygon.points.clone()).unwrap();
+    fn draw(&self, ctx: &Context, canvas: &mut Canvas) {
+        let window = ctx.window_rect();
+        canvas.background(WHITE);
 
-    second_align_thing(
-        &window,
-        &draw,
-        &mut polygon.clone(),
-        &mut bounding_box.clone(),
-        &mut model.polygon.clone(),
-        Direction::Right,
-    );
-    draw.polygon().points(polygon.points.clone()).color(RED);
+        let polygon = self.polygon.clone();
+        let bounding_box = polygon.bounding_rect().expect(NO_VERTICES_ERROR);
 
-    // put everything on the frame
-    draw.to_frame(app, &frame).unwrap();
+        second_align_thing(
+            &window,
+            canvas,
+            &mut polygon.clone(),
+            &mut bounding_box.clone(),
+            &mut self.polygon.clone(),
+            Direction::Right,
+        );
+        canvas.polygon(polygon.points.clone()).color(RED);
+    }
 }
 
 fn align_thing(
-    draw: &Draw,
+    canvas: &mut Canvas,
     polygon: &mut Polygon,
     bounding_box: &mut Rect,
     alignment_polygon: &Polygon,
     i: &mut i32,
     direction: Direction,
 ) {
-    draw.polygon()
-        .points(polygon.points.clone())
+    canvas
+        .polygon(polygon.points.clone())
         .color(if *i % 2 == 0 { LIGHTBLUE } else { LIGHTGREEN });
-    *bounding_box = bounding_rect(polygon.points.clone()).unwrap();
-    *i = *i + 1;
+    *bounding_box = polygon.bounding_rect().expect(NO_VERTICES_ERROR);
+    *i += 1;
     polygon.align(alignment_polygon, direction);
 }
 
 fn second_align_thing(
     window: &Rect,
-    draw: &Draw,
-    mut polygon: &mut Polygon,
-    mut bounding_box: &mut Rect,
+    canvas: &mut Canvas,
+    polygon: &mut Polygon,
+    bounding_box: &mut Rect,
     alignment_polygon: &mut Polygon,
     direction: Direction,
 ) {
@@ -68,38 +74,38 @@ fn second_align_thing(
         while bounding_box.y.start < window.y.end {
             let temp = polygon.clone();
             align_thing(
-                &draw,
-                &mut polygon,
-                &mut bounding_box,
+                canvas,
+                polygon,
+                bounding_box,
                 &temp,
                 &mut i,
                 Direction::Above,
             );
         }
         align_thing(
-            &draw,
-            &mut polygon,
-            &mut bounding_box,
-            &alignment_polygon,
+            canvas,
+            polygon,
+            bounding_box,
+            alignment_polygon,
             &mut i,
             Direction::Below,
         );
         while bounding_box.y.end > window.y.start {
             let temp = polygon.clone();
             align_thing(
-                &draw,
-                &mut polygon,
-                &mut bounding_box,
+                canvas,
+                polygon,
+                bounding_box,
                 &temp,
                 &mut i,
                 Direction::Below,
             );
         }
         align_thing(
-            &draw,
-            &mut polygon,
-            &mut bounding_box,
-            &alignment_polygon,
+            canvas,
+            polygon,
+            bounding_box,
+            alignment_polygon,
             &mut i,
             direction,
         );
@@ -1,57 +1,126 @@
+use crate::{
+    bindings::{Binding, Bindings},
+    canvas::Canvas,
+    config::ConfigFile,
+    simulations::three_points::{color_secondary_points, new_secondary_points},
+    sketch::{Context, Input, Sketch},
+};
 use nannou::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Action {
+    SelectOrMove,
+}
+
+const BINDINGS: &[Binding<Action>] = &[Binding::mouse(
+    Action::SelectOrMove,
+    "select_or_move",
+    MouseButton::Left,
+    "Select a point, or move the selected point to the mouse",
+)];
 
 pub struct Model {
     primary_points: [Point2; 3],
     secondary_points: Vec<(Point2, f32)>,
     selected_point: i8,
+    config: ConfigFile<Config>,
+    bindings: Bindings<Action>,
 }
 
-impl Model {
-    pub fn new(app: &App) -> Self {
-        app.new_window()
-            .view(view)
-            .mouse_pressed(mouse_pressed)
-            .build()
-            .unwrap();
+/** Constants that are read from assets/config/three_points.toml, and reloaded when it changes */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /** Size of the squares the window is coloured in */
+    point_width: f32,
+}
 
-        let window_width = app.window_rect().w();
-        let window_height = app.window_rect().h();
+impl Default for Config {
+    fn default() -> Self {
+        Config { point_width: 5. }
+    }
+}
 
+impl Sketch for Model {
+    fn init(ctx: &Context) -> Self {
+        let config = ConfigFile::<Config>::load(ctx, "three_points");
         let primary_points = [pt2(240., 63.), pt2(-127., 127.), pt2(-255., -96.)];
+        let mut secondary_points = new_secondary_points(ctx.window_rect(), config.point_width);
 
-        let mut secondary_points: Vec<(Vec2, f32)> = vec![];
-        let mut current_point = pt2(-window_width / 2., window_height / 2.);
+        color_secondary_points(primary_points, &mut secondary_points, MAX_SHADE);
+        Model {
+            primary_points,
+            secondary_points,
+            selected_point: -1,
+            config,
+            bindings: Bindings::new(ctx, "three_points", BINDINGS),
+        }
+    }
 
-        while current_point.y > -window_height / 2. {
-            secondary_points.push((current_point, 0.));
+    fn update(&mut self, ctx: &Context, _dt: f32) {
+        if self.config.reload_if_changed() {
+            self.secondary_points =
+                new_secondary_points(ctx.window_rect(), self.config.point_width);
+            color_secondary_points(self.primary_points, &mut self.secondary_points, MAX_SHADE);
+        }
+        self.bindings.reload_if_changed();
+    }
 
-            if current_point[0] + POINT_WIDTH * 1.5 > window_width / 2. {
-                current_point[0] = -window_width / 2.;
-                current_point[1] -= POINT_WIDTH;
+    fn draw(&self, _ctx: &Context, canvas: &mut Canvas) {
+        let point_width = self.config.point_width;
+        for point in &self.secondary_points {
+            if GREYSCALE {
+                canvas
+                    .rect(Rect::from_xy_wh(point.0, vec2(point_width, point_width)))
+                    .rgb(point.1, point.1, point.1);
             } else {
-                current_point[0] += POINT_WIDTH;
+                canvas
+                    .rect(Rect::from_xy_wh(point.0, vec2(point_width, point_width)))
+                    .hsv(point.1, 50., 50.);
             }
         }
 
-        color_secondary_points(primary_points, &mut secondary_points);
-        Model {
-            primary_points,
-            secondary_points,
-            selected_point: -1,
+        for (index, point) in self.primary_points.into_iter().enumerate() {
+            canvas.rect(Rect::from_xy_wh(point, vec2(15., 15.))).color(
+                if self.selected_point == index as i8 {
+                    if GREYSCALE {
+                        RED
+                    } else {
+                        WHITE
+                    }
+                } else if GREYSCALE {
+                    BLUE
+                } else {
+                    BLACK
+                },
+            );
         }
     }
-}
 
-const POINT_WIDTH: f32 = 5.;
-const GREYSCALE: bool = false;
+    fn input(&mut self, ctx: &Context, input: Input) {
+        if self.bindings.pressed(&input) == Some(Action::SelectOrMove) {
+            mouse_pressed(self, ctx.mouse());
+        }
+    }
+
+    fn counters(&self) -> Vec<(&'static str, f64)> {
+        vec![("secondary points", self.secondary_points.len() as f64)]
+    }
 
-fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
-    match button {
-        MouseButton::Left => {}
-        _ => return,
+    fn help(&self) -> Vec<(String, &'static str)> {
+        self.bindings.help()
     }
-    let mouse_point = pt2(app.mouse.x, app.mouse.y);
 
+    fn error(&self) -> Option<&str> {
+        self.config.error().or(self.bindings.error())
+    }
+}
+const GREYSCALE: bool = false;
+/** Points are shaded in grey from 0 to 1, or in hues from 0 to 255 */
+const MAX_SHADE: f32 = if GREYSCALE { 1. } else { 255. };
+
+fn mouse_pressed(model: &mut Model, mouse_point: Point2) {
     let mut pressed_point: i8 = 0;
     let width_height = vec2(15., 15.);
     for point in model.primary_points {
@@ -77,91 +146,5 @@ fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
     }
 
     model.primary_points[model.selected_point as usize] = mouse_point;
-    color_secondary_points(model.primary_points, &mut model.secondary_points);
-}
-
-pub fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-
-    for point in &model.secondary_points {
-        if GREYSCALE {
-            draw.rect()
-                .xy(point.0)
-                .wh(vec2(POINT_WIDTH, POINT_WIDTH))
-                .rgb(point.1, point.1, point.1);
-        } else {
-            draw.rect()
-                .xy(point.0)
-                .wh(vec2(POINT_WIDTH, POINT_WIDTH))
-                .hsv(point.1, 50., 50.);
-        }
-    }
-
-    let mut index = 0;
-    for point in model.primary_points {
-        draw.rect()
-            .xy(point)
-            .wh(vec2(15., 15.))
-            .color(if model.selected_point == index {
-                if GREYSCALE {
-                    RED
-                } else {
-                    WHITE
-                }
-            } else {
-                if GREYSCALE {
-                    BLUE
-                } else {
-                    BLACK
-                }
-            });
-        index += 1;
-    }
-
-    // put everything on the frame
-    draw.to_frame(app, &frame).unwrap();
-}
-
-pub fn color_secondary_points(
-    primary_points: [Point2; 3],
-    secondary_points: &mut Vec<(Vec2, f32)>,
-) {
-    // First pass: find min/max distances
-    let (min_distance, max_distance) = {
-        let mut min = f32::MAX;
-        let mut max = f32::MIN;
-
-        for (point, _) in secondary_points.iter() {
-            let total_distance = primary_points
-                .iter()
-                .map(|p| point.distance(*p))
-                .sum::<f32>()
-                / primary_points.len() as f32;
-
-            min = min.min(total_distance);
-            max = max.max(total_distance);
-        }
-
-        (min, max)
-    };
-
-    for (point, color) in secondary_points.iter_mut() {
-        let total_distance = primary_points
-            .iter()
-            .map(|p| point.distance(*p))
-            .sum::<f32>()
-            / primary_points.len() as f32;
-
-        *color = clamp(
-            map_range(
-                total_distance,
-                min_distance,
-                max_distance,
-                0.,
-                if GREYSCALE { 1. } else { 255. },
-            ),
-            0.,
-            if GREYSCALE { 1. } else { 255. },
-        );
-    }
+    color_secondary_points(model.primary_points, &mut model.secondary_points, MAX_SHADE);
 }
@@ -0,0 +1,152 @@
+use crate::headless::FRAME_TIME;
+use clap::ValueEnum;
+use nannou::image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageResult, RgbaImage,
+};
+use std::{
+    fs,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+// 1 is the best quality but very slow, 10 is the encoder's recommended trade-off
+const GIF_SPEED: i32 = 10;
+
+/**
+GIFs keep one frame in this many, as their delays are in hundredths of a second
+and viewers slow down ones under 2 to about 10, so that 60 frames a second would play far too slowly.
+Every third frame plays at 20 frames a second with a delay of 5.
+*/
+const GIF_FRAME_STEP: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// An animated GIF that loops forever
+    Gif,
+    /// A folder of numbered PNGs
+    Png,
+}
+
+impl Format {
+    /** Where a recording in this format is written, given its path without an extension */
+    pub fn path(&self, stem: PathBuf) -> PathBuf {
+        match self {
+            Format::Gif => stem.with_extension("gif"),
+            Format::Png => stem,
+        }
+    }
+}
+
+/** How many frames to record from a window, and where to put them */
+#[derive(Clone, Debug)]
+pub struct RecordOptions {
+    pub frames: u32,
+    pub format: Format,
+    /** Folder that each recording gets a new file or folder in */
+    pub out: PathBuf,
+}
+
+/**
+Frames captured at a fixed timestep of FRAME_TIME, sent to a thread that writes them as they arrive,
+so that encoding doesn't hold up the window and long recordings don't have to be kept in memory.
+*/
+pub struct Recording {
+    format: Format,
+    frames: Option<Sender<RgbaImage>>,
+    writer: Option<JoinHandle<ImageResult<()>>>,
+    path: PathBuf,
+    frames_pushed: u32,
+}
+
+impl Recording {
+    pub fn new(format: Format, path: PathBuf) -> ImageResult<Self> {
+        let (frames, received) = mpsc::channel();
+        let writer = match format {
+            Format::Gif => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let file = BufWriter::new(File::create(&path)?);
+                let mut encoder = GifEncoder::new_with_speed(file, GIF_SPEED);
+                encoder.set_repeat(Repeat::Infinite)?;
+                thread::spawn(move || write_gif(encoder, received))
+            }
+            Format::Png => {
+                fs::create_dir_all(&path)?;
+                let folder = path.clone();
+                thread::spawn(move || write_pngs(&folder, received))
+            }
+        };
+
+        Ok(Recording {
+            format,
+            frames: Some(frames),
+            writer: Some(writer),
+            path,
+            frames_pushed: 0,
+        })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn frames_pushed(&self) -> u32 {
+        self.frames_pushed
+    }
+
+    /** Every frame counts towards frames_pushed, including those a GIF leaves out */
+    pub fn push(&mut self, frame: RgbaImage) -> ImageResult<()> {
+        let kept = self.format == Format::Png || self.frames_pushed.is_multiple_of(GIF_FRAME_STEP);
+        if kept {
+            let sent = self
+                .frames
+                .as_ref()
+                .is_some_and(|frames| frames.send(frame).is_ok());
+            // The writer only hangs up when it fails, so its error says why
+            if !sent {
+                return self.join();
+            }
+        }
+        self.frames_pushed += 1;
+        Ok(())
+    }
+
+    /** Wait for the frames pushed so far to be written and finish the file */
+    pub fn finish(mut self) -> ImageResult<()> {
+        self.join()
+    }
+
+    fn join(&mut self) -> ImageResult<()> {
+        self.frames = None;
+        match self.writer.take() {
+            Some(writer) => writer.join().expect("Recording writer panicked"),
+            None => Ok(()),
+        }
+    }
+}
+
+/** The encoder writes the GIF's trailer when it is dropped at the end */
+fn write_gif(
+    mut encoder: GifEncoder<BufWriter<File>>,
+    frames: Receiver<RgbaImage>,
+) -> ImageResult<()> {
+    let milliseconds = (FRAME_TIME * GIF_FRAME_STEP as f32 * 1000.).round();
+    let delay = Delay::from_numer_denom_ms(milliseconds as u32, 1);
+    for frame in frames {
+        encoder.encode_frame(Frame::from_parts(frame, 0, 0, delay))?;
+    }
+    Ok(())
+}
+
+/** Each frame is saved to a numbered file in the folder */
+fn write_pngs(folder: &Path, frames: Receiver<RgbaImage>) -> ImageResult<()> {
+    for (index, frame) in frames.into_iter().enumerate() {
+        frame.save(folder.join(format!("{index:04}.png")))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use crate::{
+    headless::{self, RenderOptions},
+    host::{self, RunOptions},
+    projects::{
+        boids, distance_between_points, epicyclogons, game_of_life,
+        severance::{contour_lines, four_tempers, recursive_rotation},
+        tesselations::tesselation_editor,
+        three_points,
+    },
+    sketch::{self, AnySketch, Context, Sketch},
+    svg::{self, SvgOptions},
+};
+use nannou::image::{ImageResult, RgbaImage};
+use std::io;
+
+/** A sketch that can be started from the command line */
+pub struct SketchEntry {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub init: fn(&Context) -> Box<dyn AnySketch>,
+    pub run: fn(RunOptions),
+    pub render: fn(&RenderOptions) -> ImageResult<()>,
+    pub snapshot: fn(&RenderOptions) -> RgbaImage,
+    pub export_svg: fn(&SvgOptions) -> io::Result<()>,
+}
+
+const fn entry<S: Sketch>(name: &'static str, description: &'static str) -> SketchEntry {
+    SketchEntry {
+        name,
+        description,
+        init: sketch::init_boxed::<S>,
+        run: host::run::<S>,
+        render: headless::render::<S>,
+        snapshot: headless::snapshot::<S>,
+        export_svg: svg::export::<S>,
+    }
+}
+
+pub const SKETCHES: &[SketchEntry] = &[
+    entry::<boids::Model>(
+        "boids",
+        "Flocking simulation with adjustable cohesion, separation and alignment",
+    ),
+    entry::<contour_lines::Model>(
+        "contour_lines",
+        "Delaunay triangulation of random elevation points",
+    ),
+    entry::<distance_between_points::Model>(
+        "distance_between_points",
+        "Colours the plane by the distance between points on a function",
+    ),
+    entry::<epicyclogons::Model>(
+        "epicyclogons",
+        "A polygon rolling around another polygon, tracing its path",
+    ),
+    entry::<four_tempers::Model>(
+        "four_tempers",
+        "Four tempers orbiting a fixed mass under gravity",
+    ),
+    entry::<game_of_life::Model>("game_of_life", "Conway's Game of Life on a clickable grid"),
+    entry::<recursive_rotation::Model>(
+        "recursive_rotation",
+        "Recursively rotates the tiles of an image",
+    ),
+    entry::<tesselation_editor::Model>(
+        "tesselation_editor",
+        "Tiles the window with copies of a polygon",
+    ),
+    entry::<three_points::Model>(
+        "three_points",
+        "Colours the plane by the average distance to three movable points",
+    ),
+];
+
+pub fn find(name: &str) -> Option<&'static SketchEntry> {
+    SKETCHES.iter().find(|sketch| sketch.name == name)
+}
@@ -0,0 +1,279 @@
+use crate::{
+    bindings::{key_name, parse_key, Trigger},
+    clock::ClockAction,
+    presets::{self, PresetError},
+    settings_panel::Change,
+    sketch::{Context, Input, Sketch},
+};
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::Path};
+
+/**
+Everything that happened to a sketch in a run, which replays it to the exact same state.
+Times between frames are kept as well as input, as that's what the simulations advance by.
+Config files aren't recorded, so they should be left alone while recording.
+*/
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub sketch: String,
+    pub seed: u64,
+    /** Width and height of the window when the sketch was started */
+    pub window: [f32; 2],
+    pub frames: Vec<SessionFrame>,
+}
+
+/** One update of the sketch, and what happened just before it */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionFrame {
+    /** Seconds the sketch was told had passed */
+    pub dt: f32,
+    pub mouse: [f32; 2],
+    /** Width and height of the window, if it was resized since the last frame */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<[f32; 2]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<TimedEvent>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimedEvent {
+    /** Seconds since the start of the run */
+    pub time: f32,
+    pub mouse: [f32; 2],
+    pub event: Event,
+}
+
+/** Something that changed a sketch, other than time passing */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "EventRecord", into = "EventRecord")]
+pub enum Event {
+    Input(Input),
+    /** Settings changed in the settings window, with the values they changed to */
+    Settings {
+        change: Change,
+        values: serde_json::Value,
+    },
+    Clock(ClockAction),
+}
+
+/** How events are written to a file, with keys and buttons by name */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EventRecord {
+    KeyPressed {
+        key: String,
+    },
+    KeyReleased {
+        key: String,
+    },
+    MousePressed {
+        button: String,
+        position: [f32; 2],
+    },
+    Settings {
+        change: Change,
+        values: serde_json::Value,
+    },
+    Clock {
+        action: ClockAction,
+    },
+}
+
+impl From<Event> for EventRecord {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Input(Input::KeyPressed(key)) => EventRecord::KeyPressed { key: key_name(key) },
+            Event::Input(Input::KeyReleased(key)) => {
+                EventRecord::KeyReleased { key: key_name(key) }
+            }
+            Event::Input(Input::MousePressed { button, position }) => EventRecord::MousePressed {
+                button: Trigger::Mouse(button).name(),
+                position: position.to_array(),
+            },
+            Event::Settings { change, values } => EventRecord::Settings { change, values },
+            Event::Clock(action) => EventRecord::Clock { action },
+        }
+    }
+}
+
+impl TryFrom<EventRecord> for Event {
+    type Error = UnknownInput;
+
+    fn try_from(record: EventRecord) -> Result<Self, UnknownInput> {
+        let key = |name: String| parse_key(&name).ok_or(UnknownInput(name));
+        Ok(match record {
+            EventRecord::KeyPressed { key: name } => Event::Input(Input::KeyPressed(key(name)?)),
+            EventRecord::KeyReleased { key: name } => Event::Input(Input::KeyReleased(key(name)?)),
+            EventRecord::MousePressed { button, position } => match Trigger::parse(&button) {
+                Some(Trigger::Mouse(button)) => Event::Input(Input::MousePressed {
+                    button,
+                    position: Point2::from(position),
+                }),
+                _ => return Err(UnknownInput(button)),
+            },
+            EventRecord::Settings { change, values } => Event::Settings { change, values },
+            EventRecord::Clock { action } => Event::Clock(action),
+        })
+    }
+}
+
+/** A key or button in a recording that doesn't have a name in bindings */
+#[derive(Debug)]
+pub struct UnknownInput(String);
+
+impl fmt::Display for UnknownInput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no key or button called {}", self.0)
+    }
+}
+
+impl Event {
+    /** Only input with a name can be replayed, but keys without one aren't bound to anything */
+    pub fn is_recordable(&self) -> bool {
+        match self {
+            Event::Input(Input::KeyPressed(key) | Input::KeyReleased(key)) => {
+                parse_key(&key_name(*key)).is_some()
+            }
+            _ => true,
+        }
+    }
+
+    /** Do to a sketch what was done to it when the event was recorded */
+    pub fn apply<S: Sketch>(&self, sketch: &mut S, ctx: &Context) -> Result<(), PresetError> {
+        match self {
+            Event::Input(input) => sketch.input(ctx, *input),
+            Event::Settings { change, values } => {
+                sketch.apply_settings(ctx, *change, values.clone())?
+            }
+            Event::Clock(action) => {
+                if let Some(clock) = sketch.clock() {
+                    clock.apply(*action);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Session {
+    pub fn new(sketch: &str, seed: u64, window: Rect) -> Self {
+        Session {
+            sketch: sketch.to_string(),
+            seed,
+            window: window.wh().to_array(),
+            frames: vec![],
+        }
+    }
+
+    /** Read a session from a TOML or JSON file */
+    pub fn load(path: &Path) -> Result<Self, PresetError> {
+        presets::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PresetError> {
+        presets::save(path, self)
+    }
+
+    /** What the sketch was told about the world when it was started */
+    pub fn init_context(&self) -> Context {
+        Context::headless(Rect::from_wh(Vec2::from(self.window)), self.seed)
+    }
+}
+
+/** Plays a session back into a sketch one frame at a time */
+pub struct Replayer {
+    session: Session,
+    next_frame: usize,
+    ctx: Context,
+}
+
+impl Replayer {
+    pub fn new(session: Session) -> Self {
+        Replayer {
+            ctx: session.init_context(),
+            session,
+            next_frame: 0,
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /** What the sketch was told about the world in the last frame that was replayed */
+    pub fn context(&self) -> Context {
+        self.ctx
+    }
+
+    /** Apply the events of the next frame and update the sketch, returns false once there are none left */
+    pub fn replay_frame<S: Sketch>(&mut self, sketch: &mut S) -> bool {
+        let Some(frame) = self.session.frames.get(self.next_frame) else {
+            return false;
+        };
+        self.next_frame += 1;
+
+        if let Some(window) = frame.window {
+            self.ctx = Context::headless(Rect::from_wh(Vec2::from(window)), self.session.seed);
+        }
+        self.ctx = self.ctx.with_mouse(Point2::from(frame.mouse));
+        for timed in &frame.events {
+            let ctx = self.ctx.with_mouse(Point2::from(timed.mouse));
+            if let Err(error) = timed.event.apply(sketch, &ctx) {
+                eprintln!("Failed to replay {:?}: {error}", timed.event);
+            }
+        }
+        sketch.update(&self.ctx, frame.dt);
+        true
+    }
+}
+
+/** Collects a session while a sketch is run in a window */
+pub struct SessionRecorder {
+    session: Session,
+    /** Size of the window in the last frame, to notice when it is resized */
+    window: [f32; 2],
+    /** Seconds since the start of the run */
+    time: f32,
+    /** Events since the last update, which go in the next frame */
+    pending: Vec<TimedEvent>,
+}
+
+impl SessionRecorder {
+    pub fn new(session: Session) -> Self {
+        SessionRecorder {
+            window: session.window,
+            session,
+            time: 0.,
+            pending: vec![],
+        }
+    }
+
+    pub fn event(&mut self, ctx: &Context, event: Event) {
+        if event.is_recordable() {
+            self.pending.push(TimedEvent {
+                time: self.time,
+                mouse: ctx.mouse().to_array(),
+                event,
+            });
+        }
+    }
+
+    /** Call after each update of the sketch, with the time it was told had passed */
+    pub fn frame(&mut self, ctx: &Context, dt: f32) {
+        self.time += dt;
+        let window = ctx.window_rect().wh().to_array();
+        let resized = window != self.window;
+        self.window = window;
+        self.session.frames.push(SessionFrame {
+            dt,
+            mouse: ctx.mouse().to_array(),
+            window: resized.then_some(window),
+            events: std::mem::take(&mut self.pending),
+        });
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
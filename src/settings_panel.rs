@@ -0,0 +1,184 @@
+use nannou_egui::egui::{self, emath::Numeric, Response, Ui};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/** Settings that can be shown in a SettingsPanel, described one field at a time */
+pub trait PanelSettings: Clone + Default + 'static {
+    fn fields(fields: &mut Fields<Self>);
+}
+
+/** Draws the widget for a field and edits it in the settings */
+type Widget<S> = Box<dyn Fn(&mut S, &mut Ui) -> Response>;
+
+/** One labelled widget in a settings panel, editing one field of the settings */
+pub struct Field<S> {
+    label: &'static str,
+    tooltip: Option<&'static str>,
+    widget: Widget<S>,
+}
+
+impl<S> Field<S> {
+    pub fn tooltip(&mut self, tooltip: &'static str) -> &mut Self {
+        self.tooltip = Some(tooltip);
+        self
+    }
+}
+
+/** The fields of some settings, in the order they are shown */
+pub struct Fields<S> {
+    fields: Vec<Field<S>>,
+}
+
+impl<S: 'static> Fields<S> {
+    pub fn slider<T: Numeric>(
+        &mut self,
+        label: &'static str,
+        field: fn(&mut S) -> &mut T,
+        range: RangeInclusive<T>,
+    ) -> &mut Field<S> {
+        self.push(
+            label,
+            Box::new(move |settings, ui| ui.add(egui::Slider::new(field(settings), range.clone()))),
+        )
+    }
+
+    pub fn checkbox(
+        &mut self,
+        label: &'static str,
+        field: fn(&mut S) -> &mut bool,
+    ) -> &mut Field<S> {
+        self.push(
+            label,
+            Box::new(move |settings, ui| ui.checkbox(field(settings), "")),
+        )
+    }
+
+    /** Radio buttons for picking one of a few values, each with its own label */
+    pub fn choice<T: Copy + PartialEq + 'static>(
+        &mut self,
+        label: &'static str,
+        field: fn(&mut S) -> &mut T,
+        options: &'static [(T, &'static str)],
+    ) -> &mut Field<S> {
+        self.push(
+            label,
+            Box::new(move |settings, ui| {
+                let value = field(settings);
+                ui.vertical(|ui| {
+                    let mut response = ui.radio_value(value, options[0].0, options[0].1);
+                    for (option, text) in &options[1..] {
+                        response |= ui.radio_value(value, *option, *text);
+                    }
+                    response
+                })
+                .inner
+            }),
+        )
+    }
+
+    fn push(&mut self, label: &'static str, widget: Widget<S>) -> &mut Field<S> {
+        self.fields.push(Field {
+            label,
+            tooltip: None,
+            widget,
+        });
+        self.fields.last_mut().expect("A field was just pushed")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /** Edits are kept aside until "Apply changes" is pressed */
+    Apply,
+    /** Every edit is applied straight away */
+    Live,
+}
+
+/** How the applied settings changed in a frame, for the sketch to react to */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    /** A widget was edited in Live mode */
+    Edited,
+    Applied,
+    /** Settings went back to their defaults */
+    Reset,
+    /** Settings were replaced by a preset */
+    Loaded,
+}
+
+/**
+An egui panel for a sketch's settings, built from the fields they describe.
+Keeps the settings the sketch runs with apart from the ones being edited,
+and has buttons to apply or reset them and a checkbox to switch between modes.
+*/
+pub struct SettingsPanel<S: PanelSettings> {
+    applied: S,
+    unapplied: S,
+    mode: Mode,
+    fields: Vec<Field<S>>,
+}
+
+impl<S: PanelSettings> SettingsPanel<S> {
+    pub fn new(mode: Mode) -> Self {
+        SettingsPanel {
+            applied: S::default(),
+            unapplied: S::default(),
+            mode,
+            fields: {
+                let mut fields = Fields { fields: vec![] };
+                S::fields(&mut fields);
+                fields.fields
+            },
+        }
+    }
+
+    /** The settings the sketch should run with */
+    pub fn settings(&self) -> &S {
+        &self.applied
+    }
+
+    /** Replace both the applied and edited settings, like when a preset is loaded */
+    pub fn set(&mut self, settings: S) {
+        self.unapplied = settings.clone();
+        self.applied = settings;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<Change> {
+        let mut change = None;
+
+        for field in &self.fields {
+            let label = ui.label(field.label);
+            let mut response = (field.widget)(&mut self.unapplied, ui);
+            if let Some(tooltip) = field.tooltip {
+                label.on_hover_text(tooltip);
+                response = response.on_hover_text(tooltip);
+            }
+            if response.changed() && self.mode == Mode::Live {
+                change = Some(Change::Edited);
+            }
+        }
+
+        let mut live = self.mode == Mode::Live;
+        if ui.checkbox(&mut live, "Apply changes live").changed() && live {
+            // Catch up with edits made before switching
+            change = Some(Change::Applied);
+        }
+        self.mode = if live { Mode::Live } else { Mode::Apply };
+
+        ui.horizontal(|ui| {
+            if self.mode == Mode::Apply && ui.button("Apply changes").clicked() {
+                change = Some(Change::Applied);
+            }
+            if ui.button("Reset changes").clicked() {
+                self.unapplied = S::default();
+                change = Some(Change::Reset);
+            }
+        });
+
+        if change.is_some() {
+            self.applied = self.unapplied.clone();
+        }
+        change
+    }
+}
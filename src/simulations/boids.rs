@@ -0,0 +1,149 @@
+use nannou::{prelude::*, rand::Rng};
+
+/** How strongly each rule steers the boids */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    /** How far away other boids can be and still affect a boid */
+    pub visual_range: f32,
+    pub containment: f32,
+    pub cohesion: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub max_speed: f32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            visual_range: 100.,
+            containment: 5.,
+            cohesion: 1.,
+            separation: 1.,
+            alignment: 1.,
+            max_speed: 15.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Boid {
+    pub position: Point2,
+    pub velocity: Vec2,
+}
+
+impl Boid {
+    pub fn new_random<R: Rng>(boundary: &Rect, rng: &mut R) -> Self {
+        let position = pt2(
+            rng.gen_range(boundary.x.start..boundary.x.end),
+            rng.gen_range(boundary.y.start..boundary.y.end),
+        );
+
+        let angle = rng.gen_range(0. ..2. * PI);
+
+        Boid {
+            position,
+            velocity: vec2(angle.cos(), angle.sin()).normalize(),
+        }
+    }
+}
+
+pub fn new_flock<R: Rng>(size: usize, boundary: &Rect, rng: &mut R) -> Vec<Boid> {
+    (0..size).map(|_| Boid::new_random(boundary, rng)).collect()
+}
+
+/** Steer every boid by the rules of the flock, then move it by its velocity */
+pub fn step(boids: &mut [Boid], boundary: Rect, rules: &Rules) {
+    let flock = boids.to_vec();
+    for boid in boids.iter_mut() {
+        containment(boid, boundary, rules.containment);
+        cohesion(boid, &flock, rules);
+        separation(boid, &flock, rules.separation);
+        alignment(boid, &flock, rules);
+
+        if boid.velocity.length() > rules.max_speed {
+            boid.velocity = boid.velocity.normalize() * rules.max_speed;
+        }
+        boid.position += boid.velocity
+    }
+}
+
+pub fn containment(boid: &mut Boid, boundary: Rect, factor: f32) {
+    let margin = 10.;
+
+    if boid.position.x < boundary.x.start + margin {
+        boid.velocity.x += factor;
+    } else if boid.position.x > boundary.x.end - margin {
+        boid.velocity.x -= factor
+    }
+    if boid.position.y < boundary.y.start + margin {
+        boid.velocity.y += factor;
+    } else if boid.position.y > boundary.y.end - margin {
+        boid.velocity.y -= factor;
+    }
+}
+
+/** Find the center of mass of the other boids and adjust velocity slightly to point towards the center of mass. */
+pub fn cohesion(boid: &mut Boid, boids: &[Boid], rules: &Rules) {
+    let mut center_position = pt2(0., 0.);
+    let mut num_neighbors = 0;
+
+    for other_boid in boids {
+        if boid == other_boid {
+            continue;
+        }
+        if boid.position.distance(other_boid.position) > rules.visual_range {
+            continue;
+        }
+        center_position += other_boid.position;
+        num_neighbors += 1;
+    }
+
+    if num_neighbors == 0 {
+        return;
+    }
+    center_position /= num_neighbors as f32;
+    boid.velocity += (center_position - boid.position) * rules.cohesion;
+}
+
+/** Move away from other boids that are too close to avoid colliding */
+pub fn separation(boid: &mut Boid, boids: &[Boid], factor: f32) {
+    let min_distance = 20.;
+
+    let mut steer = vec2(0., 0.);
+    for other_boid in boids {
+        if boid == other_boid {
+            continue;
+        }
+        if boid.position.distance(other_boid.position) > min_distance {
+            continue; // if boid and other_boid are far enough apart
+        }
+        steer -= other_boid.position - boid.position;
+    }
+
+    boid.velocity += steer * factor;
+}
+
+// Find the average velocity (speed and direction) of the other boids and
+// adjust velocity slightly to match.
+pub fn alignment(boid: &mut Boid, boids: &[Boid], rules: &Rules) {
+    let mut avg_velocity = vec2(0., 0.);
+    let mut num_neighbours = 0;
+
+    for other_boid in boids {
+        if boid == other_boid {
+            continue;
+        }
+        if boid.position.distance(other_boid.position) > rules.visual_range {
+            continue;
+        }
+        avg_velocity += other_boid.velocity;
+        num_neighbours += 1;
+    }
+
+    if num_neighbours == 0 {
+        return;
+    }
+    avg_velocity /= num_neighbours as f32;
+
+    boid.velocity += (avg_velocity - boid.velocity) * rules.alignment;
+}
@@ -0,0 +1,91 @@
+use delaunator::{triangulate, Point};
+use nannou::{prelude::*, rand::Rng};
+use petgraph::{
+    graph::{NodeIndex, UnGraph},
+    visit::EdgeRef,
+};
+use std::collections::HashSet;
+
+/** A point on a map and how high the land is there */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContourPoint {
+    pub position: Point2,
+    pub elevation: f32,
+}
+
+/** Ten points anywhere in the window, with elevations up to 50 */
+pub fn new_points<R: Rng>(window: &Rect, rng: &mut R) -> Vec<ContourPoint> {
+    (0..10)
+        .map(|_| ContourPoint {
+            position: pt2(
+                rng.gen_range(window.x.start..window.x.end),
+                rng.gen_range(window.y.start..window.y.end),
+            ),
+            elevation: rng.gen::<f32>() * 50.,
+        })
+        .collect()
+}
+
+/** Connect the points by the edges of their Delaunay triangulation */
+pub fn delaunay_triangulation(points: Vec<ContourPoint>) -> UnGraph<ContourPoint, ()> {
+    let delaunator_points: Vec<Point> = points
+        .iter()
+        .map(|point| Point {
+            x: point.position.x as f64,
+            y: point.position.y as f64,
+        })
+        .collect();
+    let triangulation = triangulate(&delaunator_points);
+
+    // Build the graph
+    let mut graph = UnGraph::<ContourPoint, ()>::new_undirected();
+    let node_indices: Vec<NodeIndex> = (0..points.len())
+        .map(|i| graph.add_node(points[i]))
+        .collect();
+
+    // To avoid duplicate edges, use a set
+    let mut edge_set = HashSet::new();
+    for triangle in triangulation.triangles.chunks(3) {
+        let a = triangle[0];
+        let b = triangle[1];
+        let c = triangle[2];
+        // Edges: (a,b), (b,c), (c,a)
+        for &(u, v) in &[(a, b), (b, c), (c, a)] {
+            let edge = if u < v { (u, v) } else { (v, u) };
+            if edge_set.insert(edge) {
+                graph.add_edge(node_indices[u], node_indices[v], ());
+            }
+        }
+    }
+
+    graph
+}
+
+/** Where the land is at the given elevation along each edge of the triangulation */
+pub fn elevation_thing(graph: &UnGraph<ContourPoint, ()>, elevation: f32) -> Vec<Point2> {
+    let mut points = vec![];
+    for edge in graph.edge_references() {
+        let start = graph[edge.source()];
+        let end = graph[edge.target()];
+        if !is_between(elevation, start.elevation, end.elevation) {
+            continue;
+        };
+        if start.elevation < end.elevation {
+            let thing = map_range(elevation, start.elevation, end.elevation, 0., 1.);
+            points.push(start.position + thing * (end.position - start.position));
+        } else {
+            let thing = map_range(elevation, end.elevation, start.elevation, 0., 1.);
+            points.push(end.position + thing * (start.position - end.position));
+        }
+    }
+    points
+}
+
+/** Handles both increasing and decreasing intervals, and excludes NaN */
+pub fn is_between(x: f32, a: f32, b: f32) -> bool {
+    if a < b {
+        x >= a && x <= b && !x.is_nan() && !a.is_nan() && !b.is_nan()
+    } else {
+        x >= b && x <= a && !x.is_nan() && !a.is_nan() && !b.is_nan()
+    }
+}
@@ -0,0 +1,62 @@
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Functions {
+    #[default]
+    Power,
+    Rational,
+    Log,
+    Sqrt,
+}
+
+pub fn some_math_function(x: f32, function_name: &Functions) -> f32 {
+    match function_name {
+        Functions::Power => x.powi(2),
+        Functions::Rational => 1. / x,
+        Functions::Log => x.ln(),
+        Functions::Sqrt => x.sqrt(),
+    }
+}
+
+/** Points in rows and columns point_width apart, filling the window from the top left */
+pub fn new_points(window: Rect, point_width: f32) -> Vec<(Point2, f32)> {
+    let mut points: Vec<(Vec2, f32)> = vec![];
+    let mut current_point = pt2(window.x.start, window.y.end);
+
+    while current_point.y > window.y.start {
+        points.push((current_point, 0.));
+
+        if current_point[0] + point_width * 1.5 > window.x.end {
+            current_point[0] = window.x.start;
+            current_point[1] -= point_width;
+        } else {
+            current_point[0] += point_width;
+        }
+    }
+    points
+}
+
+/**
+Shade each point by how far apart the function puts its x and y, from 0 to max_shade.
+The range is found as the points are shaded, so earlier points can be shaded more brightly.
+*/
+pub fn color_points(points: &mut [(Vec2, f32)], function_name: &Functions, max_shade: f32) {
+    let mut max_distance: f32 = 0.;
+    let mut min_distance: f32 = 99999.;
+    for (point, value) in points {
+        let distance = pt2(point.x, some_math_function(point.x, function_name))
+            .distance(pt2(point.y, some_math_function(point.y, function_name)));
+
+        if max_distance < distance {
+            max_distance = distance;
+        } else if min_distance > distance {
+            min_distance = distance;
+        }
+        *value = clamp(
+            map_range(distance, min_distance, max_distance, 0., max_shade),
+            0.,
+            max_shade,
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::geometry::{angle_between_points, Direction, Polygon, Transform2D, NO_VERTICES_ERROR};
+use nannou::prelude::*;
+use std::cmp::Ordering;
+
+/**
+Two regular polygons, one fixed and one rolling around the outside of it,
+and the path traced by the lowest vertex of the rolling one.
+*/
+#[derive(Clone, Debug)]
+pub struct State {
+    pub fixed: Polygon,
+    pub rotating: Polygon,
+    // rotating_point is the point that rotating is rotating around
+    pub rotating_point: (Point2, usize),
+    // next_point is the vertex on the polygon that is next to touch the other polygon
+    pub next_point_fixed: (Point2, usize),
+    pub next_point_rotating: (Point2, usize),
+    pub traced_path: Vec<Point2>,
+    pub tracing_point: Point2,
+    pub collisions_num: u32,
+}
+
+impl State {
+    /** Start with rotating resting on top of fixed */
+    pub fn new(fixed: Polygon, mut rotating: Polygon) -> Self {
+        rotating.align(&fixed, Direction::Above);
+
+        let rotating_point;
+        let next_point_fixed;
+        let next_point_rotating;
+
+        let bounding_boxes = [
+            fixed.bounding_rect().expect(NO_VERTICES_ERROR),
+            rotating.bounding_rect().expect(NO_VERTICES_ERROR),
+        ];
+        if fixed.points.len().is_multiple_of(2) {
+            // if fixed is bigger than rotating
+            if bounding_boxes[0].w() > bounding_boxes[1].w() {
+                let bottom_right = rotating.points.len() / 4;
+                rotating_point = (rotating.points[bottom_right], bottom_right);
+                next_point_fixed = (fixed.points[0], 0);
+                if bottom_right == 0 {
+                    next_point_rotating = (
+                        rotating.points[rotating.points.len() - 1],
+                        rotating.points.len() - 1,
+                    );
+                } else {
+                    next_point_rotating = (rotating.points[bottom_right - 1], bottom_right - 1);
+                };
+            } else {
+                rotating_point = (fixed.points[0], 0);
+                next_point_fixed = (fixed.points[1], 1);
+                next_point_rotating = (
+                    rotating.points[rotating.points.len() / 4],
+                    rotating.points.len() / 4,
+                );
+            }
+        } else {
+            rotating_point = (fixed.points[fixed.points.len() - 1], fixed.points.len() - 1);
+            next_point_fixed = (fixed.points[0], 0);
+            next_point_rotating = (
+                rotating.points[rotating.points.len() / 4],
+                rotating.points.len() / 4,
+            );
+        }
+
+        let lowest_vertex_rotating = *rotating
+            .points
+            .iter()
+            .min_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+            .unwrap();
+
+        State {
+            fixed,
+            rotating,
+            rotating_point,
+            next_point_fixed,
+            next_point_rotating,
+            traced_path: vec![],
+            tracing_point: pt2(0., lowest_vertex_rotating.y),
+            collisions_num: 0,
+        }
+    }
+}
+
+/**
+Roll the rotating polygon by rotating_angle around the vertex it is resting on,
+or if that would take it into the fixed polygon, move on to the next vertex that touches
+*/
+pub fn rotate_things(state: &mut State, rotating_angle: f32) {
+    let mut angle = angle_between_points(
+        state.next_point_rotating.0,
+        state.rotating_point.0,
+        state.next_point_fixed.0,
+    );
+
+    if angle > rotating_angle {
+        angle = 2. * PI - rotating_angle;
+        let roll = Transform2D::rotate_around(state.rotating_point.0, angle);
+        state.rotating.transform(&roll);
+        state.tracing_point = roll.apply(state.tracing_point);
+
+        state.next_point_rotating.0 = state.rotating.points[state.next_point_rotating.1];
+        state.traced_path.push(state.tracing_point);
+
+        return;
+    }
+
+    state.collisions_num += 1;
+
+    let distance =
+        |polygon: &Polygon, point| polygon.distance_to_point(point).expect(NO_VERTICES_ERROR);
+    if distance(&state.rotating, state.next_point_fixed.0)
+        < distance(&state.fixed, state.next_point_rotating.0)
+    {
+        state.rotating_point = state.next_point_fixed;
+        (state.next_point_fixed).1 = if (state.next_point_fixed).1 == state.fixed.points.len() - 1 {
+            0
+        } else {
+            (state.next_point_fixed).1 + 1
+        };
+        (state.next_point_fixed).0 = state.fixed.points[(state.next_point_fixed).1];
+        state.next_point_rotating.0 = state.rotating.points[state.next_point_rotating.1];
+    } else {
+        state.rotating_point = state.next_point_rotating;
+        (state.next_point_rotating).1 = if (state.next_point_rotating).1 == 0 {
+            state.rotating.points.len() - 1
+        } else {
+            (state.next_point_rotating).1 - 1
+        };
+        (state.next_point_rotating).0 = state.rotating.points[(state.next_point_rotating).1];
+        state.next_point_fixed.0 = state.fixed.points[state.next_point_fixed.1];
+    }
+}
+
+/** Keep rolling until the polygons have collided this many times since they were created */
+pub fn roll_to_collision(state: &mut State, collisions_num: u32, rotating_angle: f32) {
+    while state.collisions_num < collisions_num {
+        rotate_things(state, rotating_angle);
+    }
+}
+
+/**
+Scale the fixed polygon around its centre at the origin and the rotating one around the point they touch,
+moving that point out with the fixed polygon's edge, so that the rolling carries on at the new sizes.
+They touch at a point with a line between them, which scaling keeps parallel, so they still don't overlap.
+*/
+pub fn resize(state: &mut State, fixed_scale: f32, rotating_scale: f32) {
+    let touching = state.rotating_point.0;
+    let moved = touching * fixed_scale;
+    state
+        .fixed
+        .transform(&Transform2D::scale(vec2(fixed_scale, fixed_scale)));
+    let rotating = Transform2D::scale_around(touching, vec2(rotating_scale, rotating_scale))
+        .then(Transform2D::translate(moved - touching));
+    state.rotating.transform(&rotating);
+    state.tracing_point = rotating.apply(state.tracing_point);
+
+    state.rotating_point.0 = moved;
+    state.next_point_fixed.0 = state.fixed.points[state.next_point_fixed.1];
+    state.next_point_rotating.0 = state.rotating.points[state.next_point_rotating.1];
+}
@@ -0,0 +1,96 @@
+use nannou::{prelude::*, rand::Rng};
+
+/** Index of the temper that sits still in the middle and only pulls on the others */
+pub const FIXED: usize = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Temper {
+    pub position: Point2,
+    pub velocity: Vec2,
+    pub acceleration: Vec2,
+    pub mass: f32,
+}
+
+impl Default for Temper {
+    fn default() -> Self {
+        Temper {
+            position: pt2(0., 0.),
+            velocity: vec2(0., 0.),
+            acceleration: vec2(0., 0.),
+            mass: 5.,
+        }
+    }
+}
+
+impl Temper {
+    pub fn new<R: Rng>(mass: f32, window: Rect, rng: &mut R) -> Self {
+        Temper {
+            position: pt2(
+                rng.gen_range(window.x.start..window.x.end),
+                rng.gen_range(window.y.start..window.y.end),
+            ),
+            mass,
+            ..Default::default()
+        }
+    }
+
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.acceleration += force / self.mass;
+    }
+
+    pub fn update(&mut self) {
+        let new_velocity = self.velocity + self.acceleration;
+        let speed = new_velocity.length();
+        if speed > 20. {
+            self.velocity = new_velocity.normalize() * 15.;
+        } else {
+            self.velocity = new_velocity;
+        }
+        self.position += self.velocity;
+        self.acceleration *= 0.;
+    }
+
+    pub fn attract(&self, other_temper: &Temper, g: f32) -> Vec2 {
+        // Calculate direction of force
+        let force = self.position - other_temper.position;
+        // Distance between objects
+        // Limiting the distance to eliminate "extreme" results for very close or very far objects
+        let distance = force.length().min(25.);
+
+        // Calculate gravitional force magnitude
+        let strength = (g * self.mass * other_temper.mass) / distance.pow(2);
+        // Get force vector --> magnitude * direction
+        force.normalize() * strength
+    }
+}
+
+/** Four tempers of the given mass somewhere in the window, and a heavier fixed one in the middle */
+pub fn new_tempers<R: Rng>(window: Rect, mass: f32, rng: &mut R) -> [Temper; 5] {
+    [
+        Temper::new(mass, window, rng),
+        Temper::new(mass, window, rng),
+        Temper::new(mass, window, rng),
+        Temper::new(mass, window, rng),
+        Temper {
+            mass: 20.,
+            ..Default::default()
+        },
+    ]
+}
+
+/** Pull every temper but the fixed one towards all the others, and move it */
+pub fn step(tempers: &mut [Temper; 5], g: f32) {
+    let others = tempers.clone();
+    for (i, temper) in tempers.iter_mut().enumerate() {
+        if i == FIXED {
+            continue;
+        }
+        for (j, other_temper) in others.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            temper.apply_force(other_temper.attract(temper, g));
+        }
+        temper.update();
+    }
+}
@@ -0,0 +1,90 @@
+use nannou::rand::Rng;
+
+/** A grid of cells for Conway's Game of Life, with nothing beyond its edges */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Life {
+    // Elements of outer Vec are the rows, bool values are the cells
+    pub cells: Vec<Vec<bool>>,
+}
+
+impl Life {
+    pub fn empty(rows: usize, columns: usize) -> Self {
+        Life {
+            cells: vec![vec![false; columns]; rows],
+        }
+    }
+
+    /** Each cell is alive with even odds */
+    pub fn random<R: Rng>(rows: usize, columns: usize, rng: &mut R) -> Self {
+        let mut life = Life::empty(rows, columns);
+        for row in &mut life.cells {
+            for cell in row {
+                *cell = rng.gen::<f32>() > 0.5;
+            }
+        }
+        life
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    /** Flip a cell between alive and dead, doing nothing if it is outside the grid */
+    pub fn toggle(&mut self, row: usize, column: usize) {
+        if let Some(cell) = self.cells.get_mut(row).and_then(|row| row.get_mut(column)) {
+            *cell ^= true;
+        }
+    }
+
+    /** Update the cells according to the rules of Conway's Game of Life. */
+    pub fn step(&mut self) {
+        self.cells = next_cells(&self.cells);
+    }
+}
+
+pub fn next_cells(cells: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let rows = cells.len();
+    let columns = cells[0].len();
+    let mut new_cells = vec![vec![false; columns]; rows];
+
+    // Iterate through the cells.
+    for i in 0..rows {
+        for j in 0..columns {
+            // Count the number of live neighbors.
+            let mut count = 0;
+            for x in -1..=1 {
+                for y in -1..=1 {
+                    if x == 0 && y == 0 {
+                        continue;
+                    }
+
+                    let i = i as i32 + x;
+                    let j = j as i32 + y;
+
+                    if i < 0 || i >= rows as i32 {
+                        continue;
+                    }
+                    if j < 0 || j >= columns as i32 {
+                        continue;
+                    }
+
+                    if cells[i as usize][j as usize] {
+                        count += 1;
+                    }
+                }
+            }
+
+            if cells[i][j] {
+                new_cells[i][j] = count == 2 || count == 3;
+            } else {
+                new_cells[i][j] = count == 3;
+            }
+        }
+    }
+
+    new_cells
+}
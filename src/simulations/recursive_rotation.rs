@@ -0,0 +1,173 @@
+use crate::geometry::PointU32;
+use nannou::image::{DynamicImage, GenericImage, GenericImageView, RgbaImage};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tile {
+    pub row: u32,
+    pub column: u32,
+}
+
+impl Tile {
+    pub fn top_left_coords(&self, tile_width: u32) -> PointU32 {
+        PointU32::new(self.row, self.column) * tile_width
+    }
+
+    /**
+    Find the tile that should be translated into the position of the tile given through the parameters
+    Works for both clockwise and anticlockwise translation
+    */
+    pub fn source_tile(&self, decreased_recursion_layers: bool) -> Tile {
+        match (
+            self.row.is_multiple_of(2),
+            self.column.is_multiple_of(2),
+            decreased_recursion_layers,
+        ) {
+            // top left increasing or top right decreasing
+            (true, true, true) | (true, false, false) => Tile {
+                row: self.row + 1,
+                column: self.column,
+            },
+            // top right increasing or bottom right decreasing
+            (true, false, true) | (false, false, false) => Tile {
+                row: self.row,
+                column: self.column - 1,
+            },
+            // bottom left increasing or top left decreasing
+            (false, true, true) | (true, true, false) => Tile {
+                row: self.row,
+                column: self.column + 1,
+            },
+            // bottom right or bottom left decreasing
+            (false, false, true) | (false, true, false) => Tile {
+                row: self.row - 1,
+                column: self.column,
+            },
+        }
+    }
+}
+
+/**
+An image whose tiles are rotated one layer deeper at a time,
+where layer n splits the image into 2^(n-1) rows and columns of tiles
+*/
+#[derive(Clone, Debug)]
+pub struct Rotation {
+    pub image: Arc<DynamicImage>,
+    pub recursion_layers: u32,
+}
+
+impl Rotation {
+    /** The image should be square, and its side length a power of 2 */
+    pub fn new(image: DynamicImage) -> Self {
+        if image.width() != image.height() {
+            panic!(
+                "Image width is {0} pixels and height is {1} pixels, but image should be square",
+                image.width(),
+                image.height()
+            );
+        }
+        if !image.width().is_power_of_two() {
+            panic!(
+                "Image width is {0}, which is not a power of 2",
+                image.width()
+            )
+        }
+
+        Rotation {
+            image: Arc::new(image),
+            recursion_layers: 1,
+        }
+    }
+
+    /** Rotate the tiles of the next layer down, going back to the second layer after single pixels */
+    pub fn rotate_deeper(&mut self) {
+        self.recursion_layers += 1;
+        if self.image.width() / find_number_of_rows(self.recursion_layers) <= 1 {
+            self.recursion_layers = 2;
+        }
+        self.image = Arc::new(process_tiles(&self.image, self.recursion_layers, false));
+    }
+
+    /** Undo the rotation of the last layer */
+    pub fn rotate_back(&mut self) {
+        if self.recursion_layers == 1 {
+            self.recursion_layers = find_number_of_layers(self.image.width()) - 1;
+        }
+        self.image = Arc::new(process_tiles(&self.image, self.recursion_layers, true));
+        self.recursion_layers -= 1;
+    }
+
+    /** The deepest layer, where the tiles are two pixels wide */
+    pub fn max_layers(&self) -> u32 {
+        find_number_of_layers(self.image.width()) - 1
+    }
+
+    /** Rotate deeper or back one layer at a time until the given layer, kept within the layers there are */
+    pub fn rotate_to(&mut self, recursion_layers: u32) {
+        let recursion_layers = recursion_layers.clamp(1, self.max_layers());
+        while self.recursion_layers < recursion_layers {
+            self.rotate_deeper();
+        }
+        while self.recursion_layers > recursion_layers {
+            self.rotate_back();
+        }
+    }
+
+    /** Go back to the first layer without changing the image */
+    pub fn reset_layers(&mut self) {
+        self.recursion_layers = 2;
+    }
+}
+
+pub fn find_number_of_rows(recursion_layers: u32) -> u32 {
+    2u32.pow(recursion_layers.max(1) - 1)
+}
+
+pub fn find_number_of_layers(rows: u32) -> u32 {
+    if rows == 0 {
+        1
+    } else {
+        rows.next_power_of_two().trailing_zeros() + 1
+    }
+}
+
+/**
+Move every tile of the layer into the place of its neighbour, turning each square of four tiles
+a quarter turn, anticlockwise or clockwise to undo it when decreased_recursion_layers
+*/
+pub fn process_tiles(
+    old_image: &DynamicImage,
+    recursion_layers: u32,
+    decreased_recursion_layers: bool,
+) -> DynamicImage {
+    let rows = find_number_of_rows(recursion_layers);
+    let tile_width = old_image.width() / rows;
+
+    let mut new_image =
+        DynamicImage::ImageRgba8(RgbaImage::new(old_image.width(), old_image.width()));
+
+    for row in 0..rows {
+        for column in 0..rows {
+            let destination_tile = Tile { row, column };
+            let source_tile = destination_tile.source_tile(decreased_recursion_layers);
+
+            let destination_tile_position = destination_tile.top_left_coords(tile_width);
+            let source_tile_position = source_tile.top_left_coords(tile_width);
+
+            for x in 0..tile_width {
+                for y in 0..tile_width {
+                    let pixel =
+                        old_image.get_pixel(source_tile_position.x + x, source_tile_position.y + y);
+                    new_image.put_pixel(
+                        destination_tile_position.x + x,
+                        destination_tile_position.y + y,
+                        pixel,
+                    );
+                }
+            }
+        }
+    }
+
+    new_image
+}
@@ -0,0 +1,62 @@
+use nannou::prelude::*;
+
+/** Points in rows and columns point_width apart, filling the window from the top left */
+pub fn new_secondary_points(window: Rect, point_width: f32) -> Vec<(Point2, f32)> {
+    let window_width = window.w();
+    let window_height = window.h();
+
+    let mut secondary_points: Vec<(Vec2, f32)> = vec![];
+    let mut current_point = pt2(-window_width / 2., window_height / 2.);
+
+    while current_point.y > -window_height / 2. {
+        secondary_points.push((current_point, 0.));
+
+        if current_point[0] + point_width * 1.5 > window_width / 2. {
+            current_point[0] = -window_width / 2.;
+            current_point[1] -= point_width;
+        } else {
+            current_point[0] += point_width;
+        }
+    }
+    secondary_points
+}
+
+/** Shade each point by its average distance to the primary points, from 0 for the closest to max_shade */
+pub fn color_secondary_points(
+    primary_points: [Point2; 3],
+    secondary_points: &mut [(Vec2, f32)],
+    max_shade: f32,
+) {
+    // First pass: find min/max distances
+    let (min_distance, max_distance) = {
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+
+        for (point, _) in secondary_points.iter() {
+            let total_distance = primary_points
+                .iter()
+                .map(|p| point.distance(*p))
+                .sum::<f32>()
+                / primary_points.len() as f32;
+
+            min = min.min(total_distance);
+            max = max.max(total_distance);
+        }
+
+        (min, max)
+    };
+
+    for (point, color) in secondary_points.iter_mut() {
+        let total_distance = primary_points
+            .iter()
+            .map(|p| point.distance(*p))
+            .sum::<f32>()
+            / primary_points.len() as f32;
+
+        *color = clamp(
+            map_range(total_distance, min_distance, max_distance, 0., max_shade),
+            0.,
+            max_shade,
+        );
+    }
+}
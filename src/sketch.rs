@@ -0,0 +1,205 @@
+use crate::{
+    canvas::Canvas,
+    clock::SimClock,
+    presets::{self, PresetError},
+    settings_panel::Change,
+};
+use nannou::{
+    app::find_assets_path,
+    prelude::*,
+    rand::{rngs::StdRng, SeedableRng},
+};
+use nannou_egui::egui::Ui;
+use std::path::{Path, PathBuf};
+
+/** How the host should create the window a sketch is shown in */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WindowOptions {
+    /** Size of the window in points, or the platform default if None */
+    pub size: Option<[u32; 2]>,
+    pub fullscreen: bool,
+}
+
+/** User input forwarded to a sketch, after egui has had a chance to consume it */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    KeyPressed(Key),
+    KeyReleased(Key),
+    MousePressed {
+        button: MouseButton,
+        position: Point2,
+    },
+}
+
+/** What a sketch can know about the world it is running in */
+#[derive(Clone, Copy, Debug)]
+pub struct Context {
+    window: Rect,
+    mouse: Point2,
+    seed: u64,
+}
+
+impl Context {
+    pub fn from_app(app: &App, seed: u64) -> Self {
+        Context {
+            window: app.window_rect(),
+            mouse: app.mouse.position(),
+            seed,
+        }
+    }
+
+    /** A context for rendering without a window, where the mouse never moves from the origin */
+    pub fn headless(window: Rect, seed: u64) -> Self {
+        Context {
+            window,
+            mouse: pt2(0., 0.),
+            seed,
+        }
+    }
+
+    /** The same context with the mouse somewhere else, like where it was when input was recorded */
+    pub fn with_mouse(self, mouse: Point2) -> Self {
+        Context { mouse, ..self }
+    }
+
+    /** Bounds of the window, centred on the origin */
+    pub fn window_rect(&self) -> Rect {
+        self.window
+    }
+
+    pub fn mouse(&self) -> Point2 {
+        self.mouse
+    }
+
+    /** The seed this run was started with, to reproduce it with --seed */
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /**
+    A random number generator seeded with the seed of this run.
+    Sketches should create one in init and keep it in their model,
+    so that everything they randomise, even after init, can be reproduced.
+    */
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+
+    pub fn assets_path(&self) -> PathBuf {
+        find_assets_path().expect("Assets folder should exist")
+    }
+}
+
+/**
+A project that can be run by the host.
+The host owns the window and egui, and calls these methods every frame.
+*/
+pub trait Sketch: 'static {
+    /** Whether the host should show a settings window built by settings_ui */
+    const HAS_SETTINGS: bool = false;
+
+    fn window() -> WindowOptions {
+        WindowOptions::default()
+    }
+
+    fn init(ctx: &Context) -> Self;
+
+    /** Advance the sketch, dt is the time since the last update in seconds */
+    fn update(&mut self, _ctx: &Context, _dt: f32) {}
+
+    fn draw(&self, ctx: &Context, canvas: &mut Canvas);
+
+    fn input(&mut self, _ctx: &Context, _input: Input) {}
+
+    /** Show the settings, returning how they changed so that the change can be recorded */
+    fn settings_ui(&mut self, _ctx: &Context, _ui: &mut Ui) -> Option<Change> {
+        None
+    }
+
+    /** The settings the sketch is running with, as they are saved in presets and recordings */
+    fn settings(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /** Replace the settings, reacting to the change as if it was made in the settings window */
+    fn apply_settings(
+        &mut self,
+        _ctx: &Context,
+        _change: Change,
+        _values: serde_json::Value,
+    ) -> Result<(), PresetError> {
+        Ok(())
+    }
+
+    /** An error to show over the sketch rather than crash on, like a config file that doesn't parse */
+    fn error(&self) -> Option<&str> {
+        None
+    }
+
+    /** The clock the sketch simulates with, for the host to pause, step and change the speed of */
+    fn clock(&mut self) -> Option<&mut SimClock> {
+        None
+    }
+
+    /** Numbers worth watching while the sketch runs, like how many boids there are, for the performance overlay */
+    fn counters(&self) -> Vec<(&'static str, f64)> {
+        vec![]
+    }
+
+    /** Keys and buttons the sketch responds to and what they do, shown in the help overlay */
+    fn help(&self) -> Vec<(String, &'static str)> {
+        vec![]
+    }
+
+    /** Start anything that would otherwise wait for input, for when nobody is at the controls */
+    fn autoplay(&mut self, _ctx: &Context) {}
+
+    /** Apply a preset file from this sketch's folder in assets/presets */
+    fn load_preset(&mut self, ctx: &Context, path: &Path) -> Result<(), PresetError> {
+        self.apply_settings(ctx, Change::Loaded, presets::load(path)?)
+    }
+}
+
+/**
+The per-frame half of Sketch, which can be boxed.
+Lets sketches be picked at runtime, like in the gallery.
+*/
+pub trait AnySketch {
+    fn update(&mut self, ctx: &Context, dt: f32);
+    fn draw(&self, ctx: &Context, canvas: &mut Canvas);
+    fn input(&mut self, ctx: &Context, input: Input);
+    fn error(&self) -> Option<&str>;
+    fn autoplay(&mut self, ctx: &Context);
+    fn load_preset(&mut self, ctx: &Context, path: &Path) -> Result<(), PresetError>;
+}
+
+impl<S: Sketch> AnySketch for S {
+    fn update(&mut self, ctx: &Context, dt: f32) {
+        Sketch::update(self, ctx, dt);
+    }
+
+    fn draw(&self, ctx: &Context, canvas: &mut Canvas) {
+        Sketch::draw(self, ctx, canvas);
+    }
+
+    fn input(&mut self, ctx: &Context, input: Input) {
+        Sketch::input(self, ctx, input);
+    }
+
+    fn error(&self) -> Option<&str> {
+        Sketch::error(self)
+    }
+
+    fn autoplay(&mut self, ctx: &Context) {
+        Sketch::autoplay(self, ctx);
+    }
+
+    fn load_preset(&mut self, ctx: &Context, path: &Path) -> Result<(), PresetError> {
+        Sketch::load_preset(self, ctx, path)
+    }
+}
+
+/** Start a sketch behind a pointer */
+pub fn init_boxed<S: Sketch>(ctx: &Context) -> Box<dyn AnySketch> {
+    Box::new(S::init(ctx))
+}
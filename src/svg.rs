@@ -0,0 +1,216 @@
+use crate::{
+    canvas::{Canvas, Geometry, Style},
+    headless::FRAME_TIME,
+    sketch::{Context, Sketch},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use nannou::{
+    color::Srgba,
+    image::{codecs::png::PngEncoder, ColorType},
+    prelude::*,
+};
+use std::{fmt::Write, fs, io, path::PathBuf};
+
+pub struct SvgOptions {
+    pub width: u32,
+    pub height: u32,
+    /** Number of updates to run before the shapes are exported */
+    pub frames: u32,
+    /** Whether to include the background, which plotters would otherwise try to fill */
+    pub background: bool,
+    pub seed: u64,
+    pub out: PathBuf,
+}
+
+/** Run a sketch without a window and write the shapes of its last frame to an SVG */
+pub fn export<S: Sketch>(options: &SvgOptions) -> io::Result<()> {
+    let window = Rect::from_w_h(options.width as f32, options.height as f32);
+    let ctx = Context::headless(window, options.seed);
+    let mut sketch = S::init(&ctx);
+    if let Some(error) = sketch.error() {
+        eprintln!("{error}");
+    }
+    sketch.autoplay(&ctx);
+    for _ in 0..options.frames {
+        sketch.update(&ctx, FRAME_TIME);
+    }
+    let mut canvas = Canvas::new();
+    sketch.draw(&ctx, &mut canvas);
+    if !options.background {
+        canvas.background = None;
+    }
+
+    if let Some(parent) = options.out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&options.out, to_svg(&canvas, window))
+}
+
+/**
+Write a canvas as an SVG document the size of window.
+Units are points, with the y axis flipped so that the document matches the window.
+*/
+pub fn to_svg(canvas: &Canvas, window: Rect) -> String {
+    let mut svg = String::new();
+    let (width, height) = window.w_h();
+    // Writing to a String can't fail, so the results of write! are ignored throughout
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}pt" height="{height}pt" viewBox="{} {} {width} {height}">"#,
+        window.left(),
+        -window.top(),
+    );
+    let _ = writeln!(svg, r#"<g transform="scale(1 -1)">"#);
+
+    if let Some(color) = canvas.background {
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{width}" height="{height}" fill="{}"{}/>"#,
+            window.left(),
+            window.bottom(),
+            css_color(color),
+            opacity("fill-opacity", color),
+        );
+    }
+
+    for shape in &canvas.shapes {
+        let style = &shape.style;
+        match &shape.geometry {
+            Geometry::Polygon(points) => {
+                // The same rule as nannou and the rasteriser, so that crossed polygons have the same holes
+                let _ = writeln!(
+                    svg,
+                    r#"<polygon points="{}" fill-rule="evenodd"{}/>"#,
+                    points_attribute(points),
+                    closed_style(style)
+                );
+            }
+            Geometry::Rect(rect) => {
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+                    rect.left(),
+                    rect.bottom(),
+                    rect.w(),
+                    rect.h(),
+                    closed_style(style)
+                );
+            }
+            Geometry::Ellipse { centre, radius } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{radius}"{}/>"#,
+                    centre.x,
+                    centre.y,
+                    closed_style(style)
+                );
+            }
+            Geometry::Polyline(points) => {
+                let _ = writeln!(
+                    svg,
+                    r#"<polyline points="{}" fill="none"{}/>"#,
+                    points_attribute(points),
+                    line_style(style)
+                );
+            }
+            Geometry::Line { start, end } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}/>"#,
+                    start.x,
+                    start.y,
+                    end.x,
+                    end.y,
+                    line_style(style)
+                );
+            }
+            Geometry::Image { image, rect } => {
+                let image = image.to_rgba8();
+                let mut png = vec![];
+                let encoded = PngEncoder::new(&mut png).encode(
+                    &image,
+                    image.width(),
+                    image.height(),
+                    ColorType::Rgba8,
+                );
+                if encoded.is_err() {
+                    continue;
+                }
+                // Images are flipped back so that they aren't drawn upside down
+                let _ = writeln!(
+                    svg,
+                    r#"<image x="{}" y="{}" width="{}" height="{}" transform="scale(1 -1)" preserveAspectRatio="none" href="data:image/png;base64,{}"/>"#,
+                    rect.left(),
+                    -rect.top(),
+                    rect.w(),
+                    rect.h(),
+                    STANDARD.encode(png)
+                );
+            }
+        }
+    }
+
+    let _ = writeln!(svg, "</g>");
+    let _ = writeln!(svg, "</svg>");
+    svg
+}
+
+fn points_attribute(points: &[Point2]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{},{}", point.x, point.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn closed_style(style: &Style) -> String {
+    let mut attributes = match style.color {
+        Some(color) => format!(
+            r#" fill="{}"{}"#,
+            css_color(color),
+            opacity("fill-opacity", color)
+        ),
+        None => r#" fill="none""#.to_string(),
+    };
+    if let Some(color) = style.stroke {
+        attributes += &format!(
+            r#" stroke="{}"{} stroke-width="{}" stroke-linejoin="miter""#,
+            css_color(color),
+            opacity("stroke-opacity", color),
+            style.stroke_weight
+        );
+    }
+    attributes
+}
+
+/** Lines and polylines are drawn with their colour, as in nannou */
+fn line_style(style: &Style) -> String {
+    match style.color {
+        Some(color) => format!(
+            r#" stroke="{}"{} stroke-width="{}""#,
+            css_color(color),
+            opacity("stroke-opacity", color),
+            style.stroke_weight
+        ),
+        None => r#" stroke="none""#.to_string(),
+    }
+}
+
+/** Channels are clamped like the rasteriser does, and NaN becomes 0 */
+fn css_color(color: Srgba) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red),
+        channel(color.green),
+        channel(color.blue)
+    )
+}
+
+fn opacity(attribute: &str, color: Srgba) -> String {
+    if color.alpha >= 1. {
+        String::new()
+    } else {
+        format!(r#" {attribute}="{}""#, color.alpha.clamp(0., 1.))
+    }
+}
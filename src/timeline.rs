@@ -0,0 +1,430 @@
+use crate::{
+    presets::{self, PresetError, Presets},
+    sketch::Context,
+};
+use nannou_egui::egui::{self, Ui};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/** How a parameter gets from one keyframe to the next */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /** Hold the value until the next keyframe, then jump to it */
+    Step,
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub const ALL: [(Easing, &'static str); 5] = [
+        (Easing::Step, "Step"),
+        (Easing::Linear, "Linear"),
+        (Easing::EaseIn, "Ease in"),
+        (Easing::EaseOut, "Ease out"),
+        (Easing::EaseInOut, "Ease in and out"),
+    ];
+
+    pub fn name(self) -> &'static str {
+        Easing::ALL
+            .iter()
+            .find(|(easing, _)| *easing == self)
+            .map_or("", |(_, name)| name)
+    }
+
+    /** How far along the way to the next value to be, for t going from 0 to 1 */
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Step => {
+                if t < 1. {
+                    0.
+                } else {
+                    1.
+                }
+            }
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1. - (1. - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4. * t * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(3) / 2.
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /** Seconds from the start of the timeline */
+    pub time: f32,
+    pub value: f64,
+    /** How to get from this keyframe to the next one */
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+/**
+Keyframes for parameters of a sketch's settings, which are addressed like in the control server,
+by the names of the fields leading to them separated by slashes.
+Only numbers can be animated, and settings that are whole numbers are rounded.
+*/
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    /** Seconds after which playback starts from the beginning again, or never if not set */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loop_after: Option<f32>,
+    /** Keyframes of each parameter, in order of time */
+    #[serde(default)]
+    pub tracks: BTreeMap<String, Vec<Keyframe>>,
+}
+
+impl Timeline {
+    /** Read a timeline from a TOML or JSON file */
+    pub fn load(path: &Path) -> Result<Self, PresetError> {
+        let mut timeline: Timeline = presets::load(path)?;
+        timeline.sort();
+        Ok(timeline)
+    }
+
+    /** Put keyframes in order of time, as files can have them in any order */
+    fn sort(&mut self) {
+        for keyframes in self.tracks.values_mut() {
+            keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PresetError> {
+        presets::save(path, self)
+    }
+
+    /** Add a keyframe to a parameter, replacing one at the same time */
+    pub fn add_keyframe(&mut self, parameter: &str, keyframe: Keyframe) {
+        let keyframes = self.tracks.entry(parameter.to_string()).or_default();
+        keyframes.retain(|other| other.time != keyframe.time);
+        let index = keyframes.partition_point(|other| other.time < keyframe.time);
+        keyframes.insert(index, keyframe);
+    }
+
+    /** When the last keyframe of any parameter is, after which nothing changes */
+    pub fn end(&self) -> f32 {
+        self.tracks
+            .values()
+            .filter_map(|keyframes| keyframes.last())
+            .map(|keyframe| keyframe.time)
+            .fold(0., f32::max)
+    }
+
+    pub fn remove_keyframe(&mut self, parameter: &str, index: usize) {
+        if let Some(keyframes) = self.tracks.get_mut(parameter) {
+            keyframes.remove(index);
+            if keyframes.is_empty() {
+                self.tracks.remove(parameter);
+            }
+        }
+    }
+
+    /** The settings with every animated parameter at its value at time, leaving the rest alone */
+    pub fn settings_at(&self, time: f32, settings: &Value) -> Value {
+        let mut settings = settings.clone();
+        for (parameter, keyframes) in &self.tracks {
+            let (Some(value), Some(slot)) = (
+                value_at(keyframes, time),
+                settings.pointer_mut(&pointer(parameter)),
+            ) else {
+                continue;
+            };
+            // Values go through the same types as the settings, so that they compare equal after being applied
+            *slot = if slot.is_f64() {
+                Value::from(value as f32)
+            } else {
+                Value::from(value.round() as i64)
+            };
+        }
+        settings
+    }
+}
+
+/** The value of a parameter at time, holding the first and last keyframes before and after them */
+pub fn value_at(keyframes: &[Keyframe], time: f32) -> Option<f64> {
+    let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+    let Some(from) = next.checked_sub(1).map(|index| keyframes[index]) else {
+        return keyframes.first().map(|keyframe| keyframe.value);
+    };
+    let Some(to) = keyframes.get(next) else {
+        return Some(from.value);
+    };
+    let t = ((time - from.time) / (to.time - from.time)) as f64;
+    Some(from.value + (to.value - from.value) * from.easing.apply(t))
+}
+
+fn pointer(parameter: &str) -> String {
+    format!("/{}", parameter.trim_start_matches('/'))
+}
+
+/** Every setting that is a number, which are the ones that can be animated */
+pub fn numeric_parameters(settings: &Value) -> Vec<String> {
+    fn collect(value: &Value, path: String, parameters: &mut Vec<String>) {
+        match value {
+            Value::Number(_) => parameters.push(path),
+            Value::Object(fields) => {
+                for (name, field) in fields {
+                    let path = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{path}/{name}")
+                    };
+                    collect(field, path, parameters);
+                }
+            }
+            _ => (),
+        }
+    }
+    let mut parameters = vec![];
+    collect(settings, String::new(), &mut parameters);
+    parameters
+}
+
+/** Where the timelines of a sketch are kept, next to its presets */
+pub fn folder(ctx: &Context, sketch: &str) -> PathBuf {
+    presets::folder(ctx, sketch).join("timelines")
+}
+
+/** Plays a timeline back by the time that passes in a sketch */
+#[derive(Clone, Debug)]
+pub struct TimelinePlayer {
+    pub timeline: Timeline,
+    time: f32,
+    playing: bool,
+    /** Set when the time jumps, so that the settings are set to it on the next frame even if paused */
+    jumped: bool,
+}
+
+impl TimelinePlayer {
+    /** Starts playing if there is anything to play */
+    pub fn new(mut timeline: Timeline) -> Self {
+        timeline.sort();
+        TimelinePlayer {
+            playing: !timeline.tracks.is_empty(),
+            timeline,
+            time: 0.,
+            jumped: true,
+        }
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn set_playing(&mut self, playing: bool) {
+        self.playing = playing;
+    }
+
+    pub fn restart(&mut self) {
+        self.time = 0.;
+        self.jumped = true;
+    }
+
+    /**
+    Move on by dt seconds if playing, returning the settings to apply
+    if the timeline has them somewhere other than where they are.
+    Settings are only set while playing up to the last keyframe, or after the time jumps,
+    so that they can be changed by other means while paused or once the timeline is over.
+    */
+    pub fn advance(&mut self, dt: f32, settings: &Value) -> Option<Value> {
+        let previous = self.time;
+        let mut jumped = std::mem::take(&mut self.jumped);
+        if self.playing {
+            self.time += dt;
+            let loop_after = self.timeline.loop_after.filter(|seconds| *seconds > 0.);
+            if let Some(loop_after) = loop_after.filter(|seconds| self.time >= *seconds) {
+                self.time %= loop_after;
+                jumped = true;
+            }
+        }
+        let playing_through = self.playing && previous <= self.timeline.end();
+        if !jumped && !playing_through {
+            return None;
+        }
+        let values = self.timeline.settings_at(self.time, settings);
+        (values != *settings).then_some(values)
+    }
+}
+
+/**
+A window for making timelines out of the settings the sketch has at a point in time,
+and for saving and loading them.
+*/
+pub struct TimelineEditor {
+    pub player: TimelinePlayer,
+    library: Presets,
+    parameter: Option<String>,
+    easing: Easing,
+}
+
+impl TimelineEditor {
+    pub fn new(ctx: &Context, sketch: &str, timeline: Timeline) -> Self {
+        TimelineEditor {
+            player: TimelinePlayer::new(timeline),
+            library: Presets::in_folder(folder(ctx, sketch), "timeline"),
+            parameter: None,
+            easing: Easing::default(),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, settings: &Value) {
+        let player = &mut self.player;
+        ui.horizontal(|ui| {
+            ui.label(format!("{:.2} s", player.time()));
+            let label = if player.playing() { "Pause" } else { "Play" };
+            if ui.button(label).clicked() {
+                player.set_playing(!player.playing());
+            }
+            if ui.button("Restart").clicked() {
+                player.restart();
+            }
+        });
+
+        ui.separator();
+        let parameters = numeric_parameters(settings);
+        egui::ComboBox::from_label("Parameter")
+            .selected_text(self.parameter.as_deref().unwrap_or("Choose a parameter"))
+            .show_ui(ui, |ui| {
+                for parameter in parameters {
+                    let text = parameter.clone();
+                    ui.selectable_value(&mut self.parameter, Some(parameter), text);
+                }
+            });
+        egui::ComboBox::from_label("Easing to the next keyframe")
+            .selected_text(self.easing.name())
+            .show_ui(ui, |ui| {
+                for (easing, name) in Easing::ALL {
+                    ui.selectable_value(&mut self.easing, easing, name);
+                }
+            });
+        let value = self
+            .parameter
+            .as_ref()
+            .and_then(|parameter| settings.pointer(&pointer(parameter))?.as_f64());
+        let add = ui
+            .add_enabled(value.is_some(), egui::Button::new("Add keyframe now"))
+            .on_hover_text("Keep the parameter at the value it has now, at this time");
+        if let (true, Some(parameter), Some(value)) = (add.clicked(), &self.parameter, value) {
+            let keyframe = Keyframe {
+                time: player.time(),
+                value,
+                easing: self.easing,
+            };
+            player.timeline.add_keyframe(parameter, keyframe);
+        }
+
+        let mut removed = None;
+        for (parameter, keyframes) in &player.timeline.tracks {
+            ui.label(parameter);
+            for (index, keyframe) in keyframes.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{:.2} s: {} ({})",
+                        keyframe.time,
+                        keyframe.value,
+                        keyframe.easing.name()
+                    ));
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some((parameter.clone(), index));
+                    }
+                });
+            }
+        }
+        if let Some((parameter, index)) = removed {
+            player.timeline.remove_keyframe(&parameter, index);
+        }
+
+        if let Some(timeline) = self.library.ui(ui, &player.timeline) {
+            *player = TimelinePlayer::new(timeline);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /** A sweep of separation from 0 to 10 over a second */
+    fn player() -> TimelinePlayer {
+        let mut timeline = Timeline::default();
+        for (time, value) in [(0., 0.), (1., 10.)] {
+            let keyframe = Keyframe {
+                time,
+                value,
+                easing: Easing::Linear,
+            };
+            timeline.add_keyframe("separation", keyframe);
+        }
+        TimelinePlayer::new(timeline)
+    }
+
+    #[test]
+    fn plays_the_keyframes() {
+        let mut player = player();
+        let settings = json!({"separation": 3.0});
+        assert_eq!(
+            player.advance(0.5, &settings),
+            Some(json!({"separation": 5.0}))
+        );
+    }
+
+    #[test]
+    fn paused_player_leaves_edited_settings_alone() {
+        let mut player = player();
+        let settings = player.advance(0.5, &json!({"separation": 0.0})).unwrap();
+        player.set_playing(false);
+        let edited = json!({"separation": 8.0});
+        assert_eq!(player.advance(0.5, &edited), None);
+        assert_eq!(player.time(), 0.5);
+
+        // Restarting jumps back to the timeline, even while paused
+        player.restart();
+        assert_eq!(
+            player.advance(0.5, &settings),
+            Some(json!({"separation": 0.0}))
+        );
+    }
+
+    #[test]
+    fn finished_player_leaves_edited_settings_alone() {
+        let mut player = player();
+        let settings = json!({"separation": 0.0});
+        assert_eq!(
+            player.advance(1.5, &settings),
+            Some(json!({"separation": 10.0}))
+        );
+        assert_eq!(player.advance(0.5, &json!({"separation": 2.0})), None);
+    }
+
+    #[test]
+    fn looping_player_starts_again() {
+        let mut player = player();
+        player.timeline.loop_after = Some(2.);
+        let settings = json!({"separation": 0.0});
+        player.advance(1.5, &settings);
+        assert_eq!(player.advance(0.25, &settings), None);
+        assert_eq!(
+            player.advance(0.5, &settings),
+            Some(json!({"separation": 2.5}))
+        );
+    }
+}
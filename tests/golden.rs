@@ -0,0 +1,139 @@
+/*!
+Renders a fixed frame of every sketch on the CPU and compares it with a reference PNG in tests/golden.
+Colours are compared by how different they look rather than exactly, so that anti-aliasing
+and rounding don't fail the test, but a sketch drawing something else does.
+
+Run with UPDATE_GOLDEN=1 to write the references again after changing what a sketch draws on purpose.
+When a sketch doesn't match, its frame and an image of where it differs are written to target/golden.
+*/
+
+use nannou::image::{Rgba, RgbaImage};
+use std::{env, path::PathBuf};
+use workshop::{
+    headless::RenderOptions,
+    record::Format,
+    registry::{self, SketchEntry},
+};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+/** Far enough in for the simulations, which are set playing, to have moved */
+const FRAMES: u32 = 30;
+const SEED: u64 = 1;
+
+/** Pixels further apart than this, from 0 for the same colour to 1 for black and white, count as different */
+const PIXEL_THRESHOLD: f64 = 0.1;
+/** Share of the pixels that can differ before a frame doesn't match */
+const MAX_DIFFERENT: f64 = 0.001;
+/** The YIQ distance between black and white, which distances are scaled by */
+const MAX_YIQ_DELTA: f64 = 35215.;
+
+fn manifest_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+fn snapshot(sketch: &SketchEntry) -> RgbaImage {
+    (sketch.snapshot)(&RenderOptions {
+        width: WIDTH,
+        height: HEIGHT,
+        frames: FRAMES,
+        format: Format::Png,
+        seed: SEED,
+        out: PathBuf::new(),
+        replay: None,
+        timeline: None,
+    })
+}
+
+/** A colour blended onto white, as it would be seen on a page */
+fn blend(pixel: Rgba<u8>) -> [f64; 3] {
+    let alpha = pixel[3] as f64 / 255.;
+    [0, 1, 2].map(|channel| 255. + (pixel[channel] as f64 - 255.) * alpha)
+}
+
+/** How different two colours look, from 0 to 1, by their distance in YIQ space weighted by how eyes see it */
+fn perceptual_delta(a: Rgba<u8>, b: Rgba<u8>) -> f64 {
+    let yiq = |[r, g, b]: [f64; 3]| {
+        [
+            r * 0.29889531 + g * 0.58662247 + b * 0.11448223,
+            r * 0.59597799 - g * 0.27417610 - b * 0.32180189,
+            r * 0.21147017 - g * 0.52261711 + b * 0.31114694,
+        ]
+    };
+    let [y1, i1, q1] = yiq(blend(a));
+    let [y2, i2, q2] = yiq(blend(b));
+    let (y, i, q) = (y1 - y2, i1 - i2, q1 - q2);
+    (0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / MAX_YIQ_DELTA
+}
+
+/**
+Count the pixels that look different, and draw them in red over a faded copy of the reference.
+Images of different sizes differ everywhere.
+*/
+fn compare(reference: &RgbaImage, actual: &RgbaImage) -> (u64, RgbaImage) {
+    if reference.dimensions() != actual.dimensions() {
+        let (width, height) = actual.dimensions();
+        let diff = RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255]));
+        return (width as u64 * height as u64, diff);
+    }
+
+    let mut different = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (expected, found) = (*reference.get_pixel(x, y), *actual.get_pixel(x, y));
+        if perceptual_delta(expected, found) > PIXEL_THRESHOLD {
+            different += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b] = blend(expected);
+            let grey = (r * 0.3 + g * 0.59 + b * 0.11) as u8;
+            Rgba([grey, grey, grey, 64])
+        }
+    });
+    (different, diff)
+}
+
+#[test]
+fn sketches_match_golden_images() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let golden = manifest_path("tests/golden");
+    let out = manifest_path("target/golden");
+    let mut failures = vec![];
+
+    for sketch in registry::SKETCHES {
+        let actual = snapshot(sketch);
+        let reference_path = golden.join(sketch.name).with_extension("png");
+        if update {
+            actual
+                .save(&reference_path)
+                .expect("Reference should be writable");
+            continue;
+        }
+        let Ok(reference) = nannou::image::open(&reference_path) else {
+            failures.push(format!(
+                "{}: no reference at {}, run with UPDATE_GOLDEN=1 to make one",
+                sketch.name,
+                reference_path.display()
+            ));
+            continue;
+        };
+
+        let (different, diff) = compare(&reference.to_rgba8(), &actual);
+        let share = different as f64 / (actual.width() * actual.height()) as f64;
+        if share > MAX_DIFFERENT {
+            std::fs::create_dir_all(&out).expect("Output folder should be writable");
+            let actual_path = out.join(format!("{}-actual.png", sketch.name));
+            let diff_path = out.join(format!("{}-diff.png", sketch.name));
+            actual.save(&actual_path).expect("Frame should be writable");
+            diff.save(&diff_path).expect("Diff should be writable");
+            failures.push(format!(
+                "{}: {different} pixels ({:.2}%) differ, see {} and {}",
+                sketch.name,
+                share * 100.,
+                actual_path.display(),
+                diff_path.display()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}